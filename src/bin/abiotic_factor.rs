@@ -1,14 +1,55 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, read_dir, read_to_string, write},
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
 use utils::prelude::*;
 
+#[derive(Debug, Parser)]
+enum WorldsCommand {
+    /// List all worlds of the server.
+    List,
+    /// Create a new world with the sandbox settings from the config template.
+    ///
+    /// The world is generated once the server is started with it.
+    Create { name: String },
+}
+
 #[derive(Debug, Parser)]
 enum SubCommand {
-    Startup,
-    Backup,
-    Update,
-    Shutdown,
+    Startup {
+        /// The world to load instead of the one from the config.
+        #[clap(long)]
+        world: Option<String>,
+    },
+    Backup {
+        /// The world to back up instead of the one from the config.
+        #[clap(long)]
+        world: Option<String>,
+    },
+    /// Manage the worlds of the server.
+    Worlds {
+        #[clap(subcommand)]
+        cmd: WorldsCommand,
+    },
+    /// Check whether a new server build is available.
+    CheckUpdate,
+    Update {
+        /// Only update if a new server build is available.
+        #[clap(long)]
+        if_needed: bool,
+    },
+    Shutdown {
+        /// Only shut the server down if no players are online.
+        #[clap(long)]
+        if_idle: bool,
+    },
+    /// Show whether the server is running, its map and its players.
+    Status,
+    /// Restore the game files from the snapshot taken before the last update.
+    Rollback,
 }
 
 #[derive(Debug, Parser)]
@@ -19,19 +60,35 @@ enum SubCommand {
 struct CliArguments {
     #[clap(subcommand)]
     pub cmd: SubCommand,
+    /// The instance to manage. Instances are declared in the `[instances.abiotic-factor]` section
+    /// of the config. The default instance is used if none is given.
+    #[clap(long, global = true)]
+    pub instance: Option<String>,
 }
 
 const GAME_NAME: &str = "abiotic-factor";
-const WORLD_SAVE_NAME: &str = "MadLab";
+/// The file in the game dir that remembers the world the server has been started with.
+const WORLD_FILE: &str = ".world";
+
+/// Make sure a world name can be safely used as a path and on the command line.
+fn ensure_valid_world_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '-'));
+    if name.is_empty() || !valid_chars {
+        bail!("Invalid world name: {name}. Only letters, digits, '_' and '-' are allowed");
+    }
 
-fn server_dir(config: &Config) -> PathBuf {
-    config
-        .game_dir()
-        .join("AbioticFactor/Saved/SaveGames/Server")
+    Ok(())
 }
 
-fn world_dir(config: &Config) -> PathBuf {
-    server_dir(config).join("Worlds").join(WORLD_SAVE_NAME)
+/// Make sure a value can be passed as a quoted argument to the server.
+fn ensure_quotable(name: &str, value: &str) -> Result<()> {
+    if value.contains('"') {
+        bail!("The {name} {value} must not contain '\"'");
+    }
+
+    Ok(())
 }
 
 fn main() -> Result<()> {
@@ -39,37 +96,211 @@ fn main() -> Result<()> {
 
     // Parse commandline options.
     let args = CliArguments::parse();
-    let server = AbioticFactor::new()?;
+    let mut server = AbioticFactor::new(args.instance)?;
 
     match args.cmd {
-        SubCommand::Startup => server.startup(),
-        SubCommand::Backup => server.backup(),
-        SubCommand::Update => server.update(),
-        SubCommand::Shutdown => server.shutdown(),
+        SubCommand::Startup { world } => {
+            server.select_world(world)?;
+            server.startup()
+        }
+        SubCommand::Backup { world } => {
+            server.select_world(world)?;
+            server.backup()
+        }
+        SubCommand::Worlds { cmd } => match cmd {
+            WorldsCommand::List => server.list_worlds(),
+            WorldsCommand::Create { name } => server.create_world(&name),
+        },
+        SubCommand::CheckUpdate => server.check_update(),
+        SubCommand::Update { if_needed } => {
+            if if_needed {
+                server.update_if_needed()
+            } else {
+                server.update()
+            }
+        }
+        SubCommand::Shutdown { if_idle } => {
+            if if_idle {
+                server.shutdown_if_idle()
+            } else {
+                server.shutdown()
+            }
+        }
+        SubCommand::Status => server.status(),
+        SubCommand::Rollback => server.rollback(),
     }
 }
 
 struct AbioticFactor {
     config: Config,
+    /// The name of the world that's loaded or backed up.
+    world: String,
 }
 
 impl AbioticFactor {
-    fn new() -> Result<Self> {
-        let config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
-        Ok(Self { config })
+    fn new(instance: Option<String>) -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.select_instance(instance)?;
+        let world = config.world(&config.abiotic_factor.world);
+        let mut server = Self { config, world };
+
+        // A running server keeps its world, even if it's been started with `--world` or the
+        // config has changed since. Backups and restarts after updates have to use it.
+        let world_file = server.world_file();
+        if world_file.exists() && server.is_running()? {
+            let world = read_to_string(&world_file)
+                .wrap_err(format!("Failed to read {world_file:?}"))?
+                .trim()
+                .to_string();
+            if !world.is_empty() {
+                server.world = world;
+            }
+        }
+        ensure_valid_world_name(&server.world)?;
+
+        Ok(server)
+    }
+
+    fn world_file(&self) -> PathBuf {
+        self.config.game_dir().join(WORLD_FILE)
+    }
+
+    /// Use another world than the one from the config.
+    fn select_world(&mut self, world: Option<String>) -> Result<()> {
+        if let Some(world) = world {
+            ensure_valid_world_name(&world)?;
+            self.world = world;
+        }
+
+        Ok(())
+    }
+
+    fn server_dir(&self) -> PathBuf {
+        self.config
+            .game_dir()
+            .join("AbioticFactor/Saved/SaveGames/Server")
+    }
+
+    fn worlds_dir(&self) -> PathBuf {
+        self.server_dir().join("Worlds")
+    }
+
+    /// The directory of the selected world.
+    fn world_dir(&self) -> PathBuf {
+        self.worlds_dir().join(&self.world)
+    }
+
+    /// Copy the sandbox settings template into a world's directory.
+    fn deploy_sandbox_settings(&self, world_dir: &Path) -> Result<()> {
+        create_dir_all(world_dir).wrap_err(format!("Failed to create {world_dir:?}"))?;
+
+        let mut secrets = HashMap::new();
+        secrets.insert("admin_steam_id", self.config.admin_steam_id.clone());
+        copy_secret_file(
+            &self
+                .config
+                .default_config_dir()
+                .join("abiotic_factor/AbioticFactor.ini"),
+            &world_dir.join("SandboxSettings.ini"),
+            &secrets,
+        )
+    }
+
+    /// Print all worlds and mark the one that's selected.
+    fn list_worlds(&self) -> Result<()> {
+        let worlds_dir = self.worlds_dir();
+        let mut worlds = Vec::new();
+        if worlds_dir.exists() {
+            for entry in read_dir(&worlds_dir).wrap_err(format!("Failed to read {worlds_dir:?}"))? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    worlds.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+        worlds.sort();
+
+        if !worlds.contains(&self.world) {
+            println!("{} (selected, created on startup)", self.world);
+        }
+        for world in worlds {
+            if world == self.world {
+                println!("{world} (selected)");
+            } else {
+                println!("{world}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_world(&self, name: &str) -> Result<()> {
+        ensure_valid_world_name(name)?;
+        let world_dir = self.worlds_dir().join(name);
+        if world_dir.exists() {
+            bail!("There's already a world with the name {name} at {world_dir:?}");
+        }
+
+        self.deploy_sandbox_settings(&world_dir)?;
+        info!(
+            "{} - Created world {name}. Start it via `startup --world {name}` or set it as `world` in the config",
+            self.session_name()
+        );
+
+        Ok(())
     }
 }
 
 impl TmuxServer for AbioticFactor {}
 
+impl SteamServer for AbioticFactor {
+    /// The abiotic factor server has the id 2857200.
+    fn app_id(&self) -> u32 {
+        2857200
+    }
+
+    /// The server only exists for windows and is run via wine.
+    fn platform(&self) -> Option<&'static str> {
+        Some("windows")
+    }
+}
+
 impl GameServer for AbioticFactor {
     fn config(&self) -> &Config {
         &self.config
     }
 
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    fn is_ready(&self) -> Result<bool> {
+        self.is_port_bound_by_session(
+            self.config.port(self.config.abiotic_factor.port)?,
+            Protocol::Udp,
+        )
+    }
+
+    fn query_client(&self) -> Result<Option<Box<dyn Query>>> {
+        Ok(Some(Box::new(A2sClient::new(format!(
+            "127.0.0.1:{}",
+            self.config.port(self.config.abiotic_factor.query_port)?
+        )))))
+    }
+
+    fn preserved_paths(&self) -> Vec<&'static str> {
+        vec![WORLD_FILE, "AbioticFactor/Saved"]
+    }
+
     fn startup_inner(&self) -> Result<()> {
         // Don't start the server if the session is already running.
         self.ensure_session_not_open()?;
+        let settings = &self.config.abiotic_factor;
+        ensure_quotable("server name", &settings.server_name)?;
+        ensure_quotable("password", &self.config.password())?;
+
+        let world_file = self.world_file();
+        write(&world_file, &self.world).wrap_err(format!("Failed to write {world_file:?}"))?;
 
         // Create a new session for this instance
         self.start_session(None)?;
@@ -83,19 +314,14 @@ impl GameServer for AbioticFactor {
                 .config
                 .default_config_dir()
                 .join("abiotic_factor/Admin.ini"),
-            &server_dir(&self.config).join("Admin.ini"),
+            &self.server_dir().join("Admin.ini"),
             &secrets,
         )?;
 
         // Copy the world config file.
-        copy_secret_file(
-            &self
-                .config
-                .default_config_dir()
-                .join("abiotic_factor/AbioticFactor.ini"),
-            &world_dir(&self.config).join("SandboxSettings.ini"),
-            &secrets,
-        )?;
+        // New worlds are generated by the server, once it's started with them.
+        info!("{} - Loading world {}", self.session_name(), self.world);
+        self.deploy_sandbox_settings(&self.world_dir())?;
 
         let mut server_command = concat!(
             "WINEDEBUG=fixme-all ",
@@ -104,17 +330,17 @@ impl GameServer for AbioticFactor {
             "-newconsole ",
             "-useperfthreads ",
             "-NoAsyncLoadingThread ",
-            r#"-SteamServerName="MadLab Hamburg" "#,
-            "-PORT=7780 ",
-            "-QueryPort=7781 ",
-            "-MaxServerPlayers=6 ",
         )
         .to_string();
-        server_command.push_str(&format!("-WorldSaveName={WORLD_SAVE_NAME} "));
+        server_command.push_str(&format!(r#"-SteamServerName="{}" "#, settings.server_name));
+        server_command.push_str(&format!("-MaxServerPlayers={} ", settings.max_players));
+        server_command.push_str(&format!("-PORT={} ", self.config.port(settings.port)?));
         server_command.push_str(&format!(
-            r#"-ServerPassword="{}" "#,
-            self.config.default_password
+            "-QueryPort={} ",
+            self.config.port(settings.query_port)?
         ));
+        server_command.push_str(&format!("-WorldSaveName={} ", self.world));
+        server_command.push_str(&format!(r#"-ServerPassword="{}" "#, self.config.password()));
 
         self.send_input_newline(&server_command)?;
 
@@ -127,42 +353,27 @@ impl GameServer for AbioticFactor {
     /// The game apparently saves automatically from time to time, so we have to rely on that.
     /// It's seemingly possible to force saving via the admin interface as well.
     fn backup_inner(&self) -> Result<()> {
-        backup_directory(
-            world_dir(&self.config),
-            self.config.create_backup_dir()?,
-            WORLD_SAVE_NAME,
-        )?;
+        // New worlds are only generated once the server has been started with them.
+        let world_dir = self.world_dir();
+        if !world_dir.exists() {
+            info!(
+                "{} - World {} doesn't exist yet, there's nothing to back up",
+                self.session_name(),
+                self.world
+            );
+            return Ok(());
+        }
+
+        backup_directory(world_dir, self.config.create_backup_dir()?, &self.world)?;
 
         Ok(())
     }
 
     fn update_inner(&self) -> Result<()> {
-        // Check if the server is running and shut it down if it is.
-        if self.is_session_open()? {
-            self.shutdown()?;
-            sleep_seconds(10);
-        }
-
         // Run a quick backup for good measure.
         self.backup()?;
 
-        // The abiotic factor server has the id 2857200 .
-        cmd!(
-            r#"steamcmd \
-        +@sSteamCmdForcePlatformType windows \
-        +force_install_dir {} \
-        +login anonymous \
-        +app_update 2857200 \
-        validate +quit"#,
-            self.config.game_dir_str()
-        )
-        .io_passthrough()
-        .run_success()?;
-
-        // Restart the server
-        self.startup()?;
-
-        Ok(())
+        self.steamcmd_update()
     }
 
     fn shutdown_inner(&self) -> Result<()> {
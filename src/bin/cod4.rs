@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use clap::Parser;
+use utils::prelude::*;
+
+#[derive(Debug, Parser)]
+enum SubCommand {
+    Startup {
+        /// The server config, i.e. one of the `cod4/*.cfg` files in the default config dir.
+        #[clap(default_value = "default")]
+        config: String,
+    },
+    Shutdown {
+        /// Only shut the server down if no players are online.
+        #[clap(long)]
+        if_idle: bool,
+    },
+    /// Show whether the server is running, its map and its players.
+    Status,
+}
+
+#[derive(Debug, Parser)]
+#[clap(name = "CoD4", about = "A small binary to manage my CoD4 server")]
+struct CliArguments {
+    #[clap(subcommand)]
+    pub cmd: SubCommand,
+    /// The instance to manage. Instances are declared in the `[instances.cod4]` section of
+    /// the config. The default instance is used if none is given.
+    #[clap(long, global = true)]
+    pub instance: Option<String>,
+}
+
+const GAME_NAME: &str = "cod4";
+/// The game port, which is also used for the `getstatus` queries.
+const PORT: u16 = 28960;
+
+fn main() -> Result<()> {
+    install_tracing()?;
+
+    // Parse commandline options.
+    let args = CliArguments::parse();
+    let mut server = Cod4::new(args.instance)?;
+
+    match args.cmd {
+        SubCommand::Startup { config } => {
+            server.server_config = config;
+            server.startup()
+        }
+        SubCommand::Shutdown { if_idle } => {
+            if if_idle {
+                server.shutdown_if_idle()
+            } else {
+                server.shutdown()
+            }
+        }
+        SubCommand::Status => server.status(),
+    }
+}
+
+struct Cod4 {
+    config: Config,
+    /// The name of the server config that's deployed on startup.
+    pub server_config: String,
+}
+
+impl Cod4 {
+    fn new(instance: Option<String>) -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.select_instance(instance)?;
+        Ok(Self {
+            config,
+            server_config: "default".to_string(),
+        })
+    }
+}
+
+impl TmuxServer for Cod4 {}
+
+impl GameServer for Cod4 {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    /// The server only answers `getstatus` requests once its map has been loaded.
+    fn is_ready(&self) -> Result<bool> {
+        match self.query_client()? {
+            Some(client) => Ok(client.query().is_ok()),
+            None => Ok(false),
+        }
+    }
+
+    fn query_client(&self) -> Result<Option<Box<dyn Query>>> {
+        Ok(Some(Box::new(Quake3Client::new(format!(
+            "127.0.0.1:{}",
+            self.config.port(PORT)?
+        )))))
+    }
+
+    fn startup_inner(&self) -> Result<()> {
+        // Don't start the server if the session is already running.
+        self.ensure_session_not_open()?;
+
+        let mut secrets = HashMap::new();
+        secrets.insert("password", self.config.password());
+        copy_secret_file(
+            &self
+                .config
+                .default_config_dir()
+                .join(format!("cod4/{}.cfg", self.server_config)),
+            &self.config.game_dir().join("main/server.cfg"),
+            &secrets,
+        )
+        .wrap_err(format!(
+            "Failed to copy {} server config",
+            self.server_config
+        ))?;
+
+        // Create a new session for this instance
+        self.start_session(None)?;
+
+        let server_command = format!(
+            "./cod4_lnxded +set dedicated 2 +set net_port {} +exec server.cfg +map_rotate",
+            self.config.port(PORT)?
+        );
+        self.send_input_newline(&server_command)?;
+
+        Ok(())
+    }
+
+    fn shutdown_inner(&self) -> Result<()> {
+        // Exit if the server is not running.
+        self.ensure_session_is_open()?;
+
+        self.send_input_newline("quit")?;
+        sleep_seconds(5);
+        self.send_input_newline("exit")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use std::{collections::HashMap, fs::create_dir_all, os::unix::fs::symlink, path::PathBuf};
+
+use clap::Parser;
+use utils::{config::Cs2Mode, prelude::*};
+
+#[derive(Debug, Parser)]
+enum MapsCommand {
+    /// Show the map pool of a mode and whether the maps are installed.
+    ///
+    /// Defaults to the `mode` of the config.
+    List { mode: Option<Cs2Mode> },
+    /// Write the map pool of a mode to the `mapcycle.txt` and `maplist.txt`.
+    ///
+    /// Defaults to the `mode` of the config.
+    Write {
+        mode: Option<Cs2Mode>,
+        /// Write the map cycle, even if some maps can't be found.
+        #[clap(long)]
+        force: bool,
+    },
+    /// Change the map of the running server.
+    Change { map: String },
+}
+
+#[derive(Debug, Parser)]
+enum SubCommand {
+    /// Start the server with a game mode preset.
+    ///
+    /// One of casual, competitive, wingman or deathmatch.
+    /// Defaults to the `mode` of the config.
+    Startup { mode: Option<Cs2Mode> },
+    Shutdown {
+        /// Only shut the server down if no players are online.
+        #[clap(long)]
+        if_idle: bool,
+    },
+    /// Show whether the server is running, its map and its players.
+    Status,
+    /// Send a command to the server console and print its output.
+    ///
+    /// Output is only available if RCON is configured.
+    Command { command: String },
+    /// Restore the game files from the snapshot taken before the last update.
+    Rollback,
+    /// Check whether a new server build is available.
+    CheckUpdate,
+    /// Manage the map pool and the current map.
+    Maps {
+        #[clap(subcommand)]
+        cmd: MapsCommand,
+    },
+    Update {
+        /// Only update if a new server build is available.
+        #[clap(long)]
+        if_needed: bool,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "CS2",
+    about = "A small binary to manage my Counter-Strike 2 server"
+)]
+struct CliArguments {
+    #[clap(subcommand)]
+    pub cmd: SubCommand,
+    /// The instance to manage. Instances are declared in the `[instances.cs2]` section of
+    /// the config. The default instance is used if none is given.
+    #[clap(long, global = true)]
+    pub instance: Option<String>,
+}
+
+const GAME_NAME: &str = "cs2";
+const PORT: u16 = 27015;
+const DEFAULT_MAP: &str = "de_dust2";
+/// The server config template.
+const CONFIG_TEMPLATE: &str = "cs2.cfg";
+
+fn main() -> Result<()> {
+    install_tracing()?;
+
+    // Parse commandline options.
+    let args = CliArguments::parse();
+    let mut server = Cs2::new(args.instance)?;
+
+    match args.cmd {
+        SubCommand::Startup { mode } => {
+            if let Some(mode) = mode {
+                server.mode = mode;
+            }
+            server.startup()
+        }
+        SubCommand::Shutdown { if_idle } => {
+            if if_idle {
+                server.shutdown_if_idle()
+            } else {
+                server.shutdown()
+            }
+        }
+        SubCommand::Status => server.status(),
+        SubCommand::Command { command } => {
+            if let Some(output) = server.send_command(&command)? {
+                println!("{output}");
+            }
+            Ok(())
+        }
+        SubCommand::Rollback => server.rollback(),
+        SubCommand::CheckUpdate => server.check_update(),
+        SubCommand::Maps { cmd } => match cmd {
+            MapsCommand::List { mode } => {
+                server.list_maps(server.config.cs2.maps(mode.unwrap_or(server.mode)))
+            }
+            MapsCommand::Write { mode, force } => {
+                server.write_map_cycle(server.config.cs2.maps(mode.unwrap_or(server.mode)), force)
+            }
+            MapsCommand::Change { map } => server.change_level(&map),
+        },
+        SubCommand::Update { if_needed } => {
+            if if_needed {
+                server.update_if_needed()
+            } else {
+                server.update()
+            }
+        }
+    }
+}
+
+struct Cs2 {
+    config: Config,
+    mode: Cs2Mode,
+}
+
+impl Cs2 {
+    fn new(instance: Option<String>) -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.select_instance(instance)?;
+        let mode = config.cs2.mode;
+        Ok(Self { config, mode })
+    }
+}
+
+impl TmuxServer for Cs2 {}
+
+impl SteamServer for Cs2 {
+    /// The CS2 dedicated server is part of the game itself, which has the id 730.
+    fn app_id(&self) -> u32 {
+        730
+    }
+
+    /// The server has always been updated without showing the output of steamcmd.
+    fn show_steamcmd_output(&self) -> bool {
+        false
+    }
+}
+
+impl SourceServer for Cs2 {
+    fn game_folder(&self) -> &'static str {
+        "game/csgo"
+    }
+
+    fn workshop_app_id(&self) -> u32 {
+        730
+    }
+
+    /// CS2 packs its maps into `.vpk` files.
+    fn map_extension(&self) -> &'static str {
+        "vpk"
+    }
+
+    /// Workshop maps are either pre-downloaded via steamcmd or downloaded by the server itself
+    /// via `host_workshop_map`, which puts them next to the binary.
+    fn workshop_dirs(&self) -> Vec<PathBuf> {
+        vec![
+            self.workshop_content_dir(),
+            self.config
+                .game_dir()
+                .join("game/bin/linuxsteamrt64/steamapps/workshop/content")
+                .join(self.workshop_app_id().to_string()),
+        ]
+    }
+}
+
+impl GameServer for Cs2 {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    fn is_ready(&self) -> Result<bool> {
+        self.is_port_bound_by_session(self.config.port(PORT)?, Protocol::Udp)
+    }
+
+    fn query_client(&self) -> Result<Option<Box<dyn Query>>> {
+        Ok(Some(Box::new(A2sClient::new(format!(
+            "127.0.0.1:{}",
+            self.config.port(PORT)?
+        )))))
+    }
+
+    fn rcon_settings(&self) -> Result<Option<RconSettings>> {
+        self.config
+            .cs2
+            .rcon
+            .as_ref()
+            .map(|rcon| rcon.settings(&self.config))
+            .transpose()
+    }
+
+    /// Configs and plugins aren't part of the installation.
+    fn preserved_paths(&self) -> Vec<&'static str> {
+        vec![
+            "game/csgo/addons",
+            "game/csgo/cfg",
+            "game/bin/linuxsteamrt64/steamapps/workshop",
+        ]
+    }
+
+    fn startup_inner(&self) -> Result<()> {
+        // Don't start the server if the session is already running.
+        self.ensure_session_not_open()?;
+
+        // Create a new session for this instance
+        self.start_session(None)?;
+
+        // CS2 expects the 64 bit steamclient.so library to be at a different location.
+        // Hence, we create a symlink to the expected location.
+        let folder = expand_home("~/.steam/sdk64/");
+        if !folder.exists() {
+            create_dir_all(folder)?;
+        }
+        let link_src = expand_home("~/.steam/steamcmd/linux64/steamclient.so");
+        let link_dest = expand_home("~/.steam/sdk64/steamclient.so");
+        if link_src.exists() && !link_dest.exists() {
+            symlink(link_src, link_dest)?;
+        }
+
+        // Load all secrets
+        let mut secrets = HashMap::new();
+        secrets.insert("password", self.config.password());
+        // An empty RCON password disables RCON.
+        let rcon_password = self
+            .rcon_settings()?
+            .map(|settings| settings.password)
+            .unwrap_or_default();
+        secrets.insert("rcon_password", rcon_password);
+
+        copy_secret_file(
+            &self.config.default_config_dir().join(CONFIG_TEMPLATE),
+            &self.game_folder_path().join("cfg/server.cfg"),
+            &secrets,
+        )?;
+
+        // Workshop maps might only be downloaded once the server has started.
+        let maps = self.config.cs2.maps(self.mode);
+        if !maps.is_empty() {
+            self.write_map_cycle(maps, true)?;
+        }
+
+        let (game_type, game_mode) = self.mode.game_type_and_mode();
+        info!("{} - Starting in {} mode", self.session_name(), self.mode);
+
+        let mut server_command = concat!(
+            "./game/bin/linuxsteamrt64/cs2 ",
+            "-dedicated ",
+            "-console ",
+            "-usercon ",
+            "-ip 0.0.0.0 ",
+        )
+        .to_string();
+        server_command.push_str(&format!(
+            "-port {} +game_type {game_type} +game_mode {game_mode} +map {} ",
+            self.config.port(PORT)?,
+            self.config.world(DEFAULT_MAP)
+        ));
+        server_command.push_str(&format!(
+            "+sv_setsteamaccount {} ",
+            self.config.cs2.login_token
+        ));
+
+        self.send_input_newline(&server_command)?;
+
+        Ok(())
+    }
+
+    fn update_inner(&self) -> Result<()> {
+        self.steamcmd_update()
+    }
+
+    fn shutdown_inner(&self) -> Result<()> {
+        // Exit if the server is not running.
+        self.ensure_session_is_open()?;
+
+        self.send_ctrl_c()?;
+        self.send_input_newline("exit")?;
+
+        Ok(())
+    }
+}
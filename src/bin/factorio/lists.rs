@@ -0,0 +1,186 @@
+use std::{
+    fs::{create_dir_all, read_to_string, write},
+    path::PathBuf,
+};
+
+use serde_json::{Value, json};
+use utils::prelude::*;
+
+use crate::Factorio;
+
+/// The player lists of the factorio server.
+#[derive(Clone, Copy, Debug)]
+pub enum PlayerList {
+    Whitelist,
+    Admins,
+    Bans,
+}
+
+impl PlayerList {
+    /// The name of the list file in the `config` directory.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            PlayerList::Whitelist => "server-whitelist.json",
+            PlayerList::Admins => "server-adminlist.json",
+            PlayerList::Bans => "server-banlist.json",
+        }
+    }
+
+    /// The console command that adds a player to this list on a running server.
+    fn add_command(&self, player: &str, reason: Option<&str>) -> String {
+        match self {
+            PlayerList::Whitelist => format!("/whitelist add {player}"),
+            PlayerList::Admins => format!("/promote {player}"),
+            PlayerList::Bans => format!("/ban {player} {}", reason.unwrap_or_default()),
+        }
+    }
+
+    /// The console command that removes a player from this list on a running server.
+    fn remove_command(&self, player: &str) -> String {
+        match self {
+            PlayerList::Whitelist => format!("/whitelist remove {player}"),
+            PlayerList::Admins => format!("/demote {player}"),
+            PlayerList::Bans => format!("/unban {player}"),
+        }
+    }
+}
+
+/// Get the player name of a list entry.
+///
+/// Entries are usually plain names, but the banlist also contains objects with a username and
+/// the reason of the ban.
+fn entry_name(entry: &Value) -> Option<&str> {
+    entry
+        .as_str()
+        .or_else(|| entry.get("username").and_then(Value::as_str))
+}
+
+/// Make sure some input can be safely sent to the server console.
+fn ensure_console_safe(input: &str) -> Result<()> {
+    if input.contains(['\'', '\n']) {
+        bail!("Input must not contain quotes or newlines: {input}");
+    }
+
+    Ok(())
+}
+
+impl Factorio {
+    pub fn player_list_path(&self, list: PlayerList) -> PathBuf {
+        self.config.game_dir().join("config").join(list.file_name())
+    }
+
+    fn read_player_list(&self, list: PlayerList) -> Result<Vec<Value>> {
+        let path = self.player_list_path(list);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = read_to_string(&path).wrap_err(format!("Failed to read {path:?}"))?;
+        serde_json::from_str(&content).wrap_err(format!("Failed to parse {path:?}"))
+    }
+
+    fn write_player_list(&self, list: PlayerList, entries: &[Value]) -> Result<()> {
+        let path = self.player_list_path(list);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(entries)?;
+        write(&path, content).wrap_err(format!("Failed to write {path:?}"))?;
+
+        Ok(())
+    }
+
+    /// Print all players on the given list.
+    ///
+    /// The list is read from disk, so changes that have been made via the console of a running
+    /// server only show up once the server has written them.
+    pub fn print_player_list(&self, list: PlayerList) -> Result<()> {
+        for entry in self.read_player_list(list)? {
+            let Some(name) = entry_name(&entry) else {
+                continue;
+            };
+
+            match entry.get("reason").and_then(Value::as_str) {
+                Some(reason) if !reason.is_empty() => println!("{name}: {reason}"),
+                _ => println!("{name}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add a player to a list.
+    ///
+    /// If the server is running, this is done via the console, so the change takes effect
+    /// immediately. Otherwise, the list file is edited.
+    pub fn add_to_player_list(
+        &self,
+        list: PlayerList,
+        player: &str,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        ensure_console_safe(player)?;
+        if let Some(reason) = reason {
+            ensure_console_safe(reason)?;
+        }
+
+        if self.is_session_open()? {
+            if let Some(output) = self.send_command(&list.add_command(player, reason))? {
+                println!("{output}");
+            }
+            info!("Added {player} to {} via console", list.file_name());
+            return Ok(());
+        }
+
+        let mut entries = self.read_player_list(list)?;
+        if entries
+            .iter()
+            .any(|entry| entry_name(entry) == Some(player))
+        {
+            info!("{player} is already in {}", list.file_name());
+            return Ok(());
+        }
+
+        let entry = match list {
+            PlayerList::Bans => json!({
+                "username": player,
+                "reason": reason.unwrap_or_default(),
+            }),
+            _ => json!(player),
+        };
+        entries.push(entry);
+        self.write_player_list(list, &entries)?;
+        info!("Added {player} to {}", list.file_name());
+
+        Ok(())
+    }
+
+    /// Remove a player from a list.
+    ///
+    /// Just like [Factorio::add_to_player_list], this uses the console if the server is running.
+    pub fn remove_from_player_list(&self, list: PlayerList, player: &str) -> Result<()> {
+        ensure_console_safe(player)?;
+
+        if self.is_session_open()? {
+            if let Some(output) = self.send_command(&list.remove_command(player))? {
+                println!("{output}");
+            }
+            info!("Removed {player} from {} via console", list.file_name());
+            return Ok(());
+        }
+
+        let mut entries = self.read_player_list(list)?;
+        let count = entries.len();
+        entries.retain(|entry| entry_name(entry) != Some(player));
+        if entries.len() == count {
+            info!("{player} isn't in {}", list.file_name());
+            return Ok(());
+        }
+
+        self.write_player_list(list, &entries)?;
+        info!("Removed {player} from {}", list.file_name());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,607 @@
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, read_dir, remove_dir_all, remove_file, rename},
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use serde_derive::Deserialize;
+use utils::{http, prelude::*, snapshot::move_paths};
+
+mod lists;
+mod maps;
+mod mods;
+
+use lists::PlayerList;
+
+#[derive(Debug, Parser)]
+enum PlayerListCommand {
+    /// Add a player to the list.
+    Add { player: String },
+    /// Remove a player from the list.
+    Remove { player: String },
+    /// Show all players on the list.
+    List,
+}
+
+#[derive(Debug, Parser)]
+enum BanCommand {
+    /// Ban a player.
+    Add {
+        player: String,
+        /// The reason that's shown to the player.
+        reason: Option<String>,
+    },
+    /// Unban a player.
+    Remove { player: String },
+    /// Show all banned players.
+    List,
+}
+
+#[derive(Debug, Parser)]
+enum ModsCommand {
+    /// List the declared and the installed mods.
+    List,
+    /// Download missing mods, remove stale ones and write the `mod-list.json`.
+    Sync,
+}
+
+#[derive(Debug, Parser)]
+enum SubCommand {
+    Startup {
+        /// The name of a save in the `saves` directory that should be loaded.
+        /// The instance's `world` or the latest save is loaded by default.
+        #[clap(long)]
+        save: Option<String>,
+    },
+    Shutdown,
+    /// Update the game to a specific version.
+    /// The version is expected in the `1.1.34` format.
+    ///
+    /// `latest` and `stable` resolve to the newest experimental or stable release.
+    Update {
+        version: String,
+    },
+    Backup,
+    /// Restore the game files from the snapshot taken before the last update.
+    Rollback,
+    /// Send a command to the server console and print its output.
+    ///
+    /// Output is only available if RCON is configured.
+    Command {
+        command: String,
+    },
+    /// Create a new map with the map settings from the default config dir.
+    NewMap {
+        /// The name of the new save.
+        name: String,
+        /// The map seed. A random seed is used by default.
+        #[clap(long)]
+        seed: Option<u32>,
+    },
+    /// Manage the mods declared in the config.
+    Mods {
+        #[clap(subcommand)]
+        cmd: ModsCommand,
+    },
+    /// Manage the players that're allowed to join.
+    Whitelist {
+        #[clap(subcommand)]
+        cmd: PlayerListCommand,
+    },
+    /// Manage the server admins.
+    Admins {
+        #[clap(subcommand)]
+        cmd: PlayerListCommand,
+    },
+    /// Manage banned players.
+    Bans {
+        #[clap(subcommand)]
+        cmd: BanCommand,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "Factorio",
+    about = "A small binary to manage my factorio server"
+)]
+struct CliArguments {
+    #[clap(subcommand)]
+    pub cmd: SubCommand,
+    /// The instance to manage. Instances are declared in the `[instances.factorio]` section of
+    /// the config. The default instance is used if none is given.
+    #[clap(long, global = true)]
+    pub instance: Option<String>,
+}
+
+const GAME_NAME: &str = "factorio";
+const PORT: u16 = 34197;
+
+fn main() -> Result<()> {
+    install_tracing()?;
+
+    // Parse commandline options.
+    let args = CliArguments::parse();
+
+    match args.cmd {
+        SubCommand::Startup { save } => {
+            let mut server = Factorio::new(args.instance)?;
+            server.save = save;
+            server.startup()
+        }
+        SubCommand::Shutdown => {
+            let server = Factorio::new(args.instance)?;
+            server.shutdown()
+        }
+        SubCommand::Backup => {
+            let server = Factorio::new(args.instance)?;
+            server.backup()
+        }
+        SubCommand::Update { version } => {
+            let server = Factorio::new_with_version(args.instance, version)?;
+            server.update()
+        }
+        SubCommand::Rollback => {
+            let server = Factorio::new(args.instance)?;
+            server.rollback()
+        }
+        SubCommand::Command { command } => {
+            let server = Factorio::new(args.instance)?;
+            if let Some(output) = server.send_command(&command)? {
+                println!("{output}");
+            }
+            Ok(())
+        }
+        SubCommand::NewMap { name, seed } => {
+            let server = Factorio::new(args.instance)?;
+            server.new_map(&name, seed)
+        }
+        SubCommand::Mods { cmd } => {
+            let server = Factorio::new(args.instance)?;
+            match cmd {
+                ModsCommand::List => server.list_mods(),
+                ModsCommand::Sync => server.sync_mods(),
+            }
+        }
+        SubCommand::Whitelist { cmd } => {
+            let server = Factorio::new(args.instance)?;
+            server.manage_player_list(PlayerList::Whitelist, cmd)
+        }
+        SubCommand::Admins { cmd } => {
+            let server = Factorio::new(args.instance)?;
+            server.manage_player_list(PlayerList::Admins, cmd)
+        }
+        SubCommand::Bans { cmd } => {
+            let server = Factorio::new(args.instance)?;
+            match cmd {
+                BanCommand::Add { player, reason } => {
+                    server.add_to_player_list(PlayerList::Bans, &player, reason.as_deref())
+                }
+                BanCommand::Remove { player } => {
+                    server.remove_from_player_list(PlayerList::Bans, &player)
+                }
+                BanCommand::List => server.print_player_list(PlayerList::Bans),
+            }
+        }
+    }
+}
+
+/// The response of factorio's `/api/latest-releases` endpoint.
+#[derive(Debug, Deserialize)]
+struct LatestReleases {
+    experimental: Releases,
+    stable: Releases,
+}
+
+#[derive(Debug, Deserialize)]
+struct Releases {
+    headless: String,
+}
+
+impl LatestReleases {
+    /// The headless version for `latest` (experimental) or `stable`.
+    fn headless(self, version: &str) -> String {
+        match version {
+            "latest" => self.experimental.headless,
+            _ => self.stable.headless,
+        }
+    }
+}
+
+/// Find the checksum of the headless tarball of a version in factorio's `sha256sums` file.
+///
+/// The lines look like this: `{sha256}  factorio-headless_linux_2.0.28.tar.xz`
+fn find_checksum(checksums: &str, version: &str) -> Result<String> {
+    checksums
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| {
+            let name = name.trim();
+            name.contains("headless") && name.ends_with(&format!("_{version}.tar.xz"))
+        })
+        .map(|(checksum, _)| checksum.to_lowercase())
+        .ok_or_else(|| eyre!("Couldn't find checksum for version {version}"))
+}
+
+struct Factorio {
+    config: Config,
+    version: Option<String>,
+    /// The save that should be loaded on startup instead of the latest one.
+    save: Option<String>,
+}
+
+impl Factorio {
+    fn new(instance: Option<String>) -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.select_instance(instance)?;
+        Ok(Self {
+            config,
+            version: None,
+            save: None,
+        })
+    }
+
+    fn new_with_version(instance: Option<String>, version: String) -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.select_instance(instance)?;
+        Ok(Self {
+            config,
+            version: Some(version),
+            save: None,
+        })
+    }
+
+    fn manage_player_list(&self, list: PlayerList, cmd: PlayerListCommand) -> Result<()> {
+        match cmd {
+            PlayerListCommand::Add { player } => self.add_to_player_list(list, &player, None),
+            PlayerListCommand::Remove { player } => self.remove_from_player_list(list, &player),
+            PlayerListCommand::List => self.print_player_list(list),
+        }
+    }
+
+    /// Resolve `latest` and `stable` to the respective version number.
+    /// Any other version is returned as it is.
+    fn resolve_version(&self, version: &str) -> Result<String> {
+        if version != "latest" && version != "stable" {
+            return Ok(version.to_string());
+        }
+
+        let url = format!("{}/api/latest-releases", self.config.factorio.url);
+        let releases: LatestReleases =
+            http::get_json(&url).wrap_err("Failed to get latest factorio releases")?;
+
+        let resolved = releases.headless(version);
+        info!("Resolved {version} to version {resolved}");
+
+        Ok(resolved)
+    }
+
+    /// Download the headless server tarball and verify its checksum.
+    fn download(&self, version: &str, dest: &Path) -> Result<()> {
+        let url = format!(
+            "{}/get-download/{version}/headless/linux64",
+            self.config.factorio.url
+        );
+        http::download(&url, dest)?;
+
+        // Factorio publishes the checksums of all releases in a single file.
+        let checksums_url = format!("{}/download/sha256sums/", self.config.factorio.url);
+        let checksums = http::get_text(&checksums_url)?;
+        let expected = find_checksum(&checksums, version)?;
+
+        let actual = http::sha256_file(dest)?;
+        if actual != expected {
+            bail!("Checksum mismatch for {dest:?}. Expected {expected}, got {actual}");
+        }
+        info!("Checksum of {dest:?} is valid");
+
+        Ok(())
+    }
+
+    /// Remove all downloaded server tarballs from the system's temp directory.
+    fn remove_downloads(&self) -> Result<()> {
+        for entry in read_dir(std::env::temp_dir())? {
+            let path = entry?.path();
+            let is_download = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("factorio_headless_"));
+            if is_download && path.is_file() {
+                info!("Removing {path:?}");
+                remove_file(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Download and install the given version.
+    fn install(&self, version: &str, tar_path: &Path) -> Result<()> {
+        self.download(version, tar_path)?;
+
+        // Extract the server files into a staging directory next to the game dir first.
+        // That way, the current installation stays untouched if the extraction fails and all
+        // files can be renamed, as they're on the same filesystem.
+        info!("Extracting file");
+        let game_dir = self.config.game_dir();
+        let staging_dir = sibling_dir(&game_dir, "staging");
+        if staging_dir.exists() {
+            remove_dir_all(&staging_dir)?;
+        }
+        create_dir_all(&staging_dir)?;
+        cmd!(
+            "tar xf {} -C {}",
+            tar_path.to_string_lossy(),
+            staging_dir.to_string_lossy()
+        )
+        .run_success()?;
+
+        // The tarball contains a single `factorio` directory with the new installation.
+        let new_install_dir = staging_dir.join("factorio");
+
+        // Move all important files over to the new installation.
+        // If anything goes wrong, they're moved back, so no saves get lost.
+        info!("Moving game files to new installation.");
+        let preserved = self.preserved_paths();
+        let result = move_paths(&game_dir, &new_install_dir, &preserved)
+            .and_then(|_| replace_dir(&new_install_dir, &game_dir));
+        if let Err(error) = result {
+            move_paths(&new_install_dir, &game_dir, &preserved)
+                .wrap_err("Failed to move the preserved files back to the old installation")?;
+            return Err(error);
+        }
+        remove_dir_all(&staging_dir)?;
+
+        Ok(())
+    }
+}
+
+/// Quote a value, so it's passed as a single argument by the shell in the tmux session.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// A directory next to the given one, e.g. `factorio.staging` for `factorio`.
+fn sibling_dir(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{suffix}"));
+    dir.with_file_name(name)
+}
+
+/// Replace a directory with another one.
+///
+/// The old directory is only removed, once the new one is in place.
+fn replace_dir(new: &Path, dir: &Path) -> Result<()> {
+    if !dir.exists() {
+        return rename(new, dir).wrap_err(format!("Failed to move {new:?} to {dir:?}"));
+    }
+
+    let old = sibling_dir(dir, "old");
+    if old.exists() {
+        remove_dir_all(&old)?;
+    }
+    rename(dir, &old).wrap_err(format!("Failed to move {dir:?} to {old:?}"))?;
+    if let Err(error) = rename(new, dir) {
+        rename(&old, dir).wrap_err(format!("Failed to restore {dir:?}"))?;
+        return Err(error).wrap_err(format!("Failed to move {new:?} to {dir:?}"));
+    }
+    remove_dir_all(&old)?;
+
+    Ok(())
+}
+
+impl TmuxServer for Factorio {}
+
+impl GameServer for Factorio {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    /// The server only binds its port once the map has been loaded.
+    fn is_ready(&self) -> Result<bool> {
+        self.is_port_bound_by_session(self.config.port(PORT)?, Protocol::Udp)
+    }
+
+    fn rcon_settings(&self) -> Result<Option<RconSettings>> {
+        self.config
+            .factorio
+            .rcon
+            .as_ref()
+            .map(|rcon| rcon.settings(&self.config))
+            .transpose()
+    }
+
+    fn preserved_paths(&self) -> Vec<&'static str> {
+        vec!["saves", "config", "mods", "mod-settings.json"]
+    }
+
+    fn startup_inner(&self) -> Result<()> {
+        // Exit if the server is not running.
+        self.ensure_session_not_open()?;
+
+        // Load all secrets
+        let mut secrets = HashMap::new();
+        secrets.insert("password", self.config.password());
+
+        // Deploy the server config file
+        let server_config_path = self
+            .config
+            .game_dir()
+            .join("config/custom-server-config.json");
+        copy_secret_file(
+            &self
+                .config
+                .default_config_dir()
+                .join("factorio-server-settings.json"),
+            &server_config_path,
+            &secrets,
+        )
+        .wrap_err("Failed while copying server config file")?;
+
+        // Make sure the declared mods are installed.
+        // The mods directory isn't managed if no mods are declared.
+        if !self.config.factorio.mods.is_empty() {
+            self.sync_mods().wrap_err("Failed to sync mods")?;
+        }
+
+        // Either load the selected save, the instance's world or the latest one.
+        let save = self
+            .save
+            .clone()
+            .or_else(|| self.config.instance_settings().world);
+        let save_option = match &save {
+            Some(save) => {
+                let save_path = self.save_path(save)?;
+                if !save_path.exists() {
+                    bail!("Couldn't find save {save} at {save_path:?}");
+                }
+                format!(
+                    "--start-server {}",
+                    shell_quote(&save_path.to_string_lossy())
+                )
+            }
+            None => "--start-server-load-latest".to_string(),
+        };
+
+        // Create a new session for this instance
+        self.start_session(None)?;
+
+        let mut server_command = format!(
+            "{}/bin/x64/factorio \
+        {save_option} \
+        --port {} \
+        --use-server-whitelist \
+        --server-whitelist {} \
+        --server-settings {}",
+            self.config.game_dir_str(),
+            self.config.port(PORT)?,
+            self.player_list_path(PlayerList::Whitelist)
+                .to_string_lossy(),
+            server_config_path.to_string_lossy(),
+        );
+
+        // Only pass the admin and ban lists, if they exist.
+        let adminlist = self.player_list_path(PlayerList::Admins);
+        if adminlist.exists() {
+            server_command.push_str(&format!(
+                " --server-adminlist {}",
+                adminlist.to_string_lossy()
+            ));
+        }
+        let banlist = self.player_list_path(PlayerList::Bans);
+        if banlist.exists() {
+            server_command.push_str(&format!(" --server-banlist {}", banlist.to_string_lossy()));
+        }
+
+        if let Some(rcon) = self.rcon_settings()? {
+            server_command.push_str(&format!(
+                " --rcon-bind {} --rcon-password {}",
+                rcon.address,
+                shell_quote(&rcon.password)
+            ));
+        }
+
+        // Start the server
+        self.send_input_newline(&server_command)?;
+
+        Ok(())
+    }
+
+    fn backup_inner(&self) -> Result<()> {
+        let save_file = get_newest_file(&self.config.game_dir().join("saves"))?;
+        if let Some(file_to_backup) = save_file {
+            backup_file(
+                file_to_backup,
+                self.config.create_backup_dir()?,
+                "factorio",
+                "zip",
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn update_inner(&self) -> Result<()> {
+        let version = self
+            .version
+            .as_ref()
+            .ok_or_else(|| eyre!("Version not specified for update"))?;
+        let version = self.resolve_version(version)?;
+
+        let tar_path = std::env::temp_dir().join(format!("factorio_headless_x64_{version}.tar.xz"));
+        let result = self.install(&version, &tar_path);
+
+        // Always clean up the downloaded files, even if the installation failed.
+        self.remove_downloads()
+            .wrap_err("Failed to remove downloaded files")?;
+
+        result
+    }
+
+    fn shutdown_inner(&self) -> Result<()> {
+        // Exit if the server is not running.
+        self.ensure_session_is_open()?;
+
+        // Send Ctrl+C and wait a few seconds to save the map and shutdown the server
+        self.send_ctrl_c()?;
+
+        info!("Giving the server some time to shut down.");
+        sleep_seconds(5);
+
+        // Backup the map
+        self.backup().wrap_err("Failed during backup:")?;
+
+        // Exit the session
+        self.send_input_newline("exit")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_latest_and_stable() -> Result<()> {
+        let response = r#"{
+            "experimental": {"alpha": "2.0.30", "demo": "1.1.110", "expansion": "2.0.30", "headless": "2.0.30"},
+            "stable": {"alpha": "2.0.28", "demo": "1.1.110", "expansion": "2.0.28", "headless": "2.0.28"}
+        }"#;
+
+        let releases: LatestReleases = serde_json::from_str(response)?;
+        assert_eq!(releases.headless("latest"), "2.0.30");
+        let releases: LatestReleases = serde_json::from_str(response)?;
+        assert_eq!(releases.headless("stable"), "2.0.28");
+
+        Ok(())
+    }
+
+    #[test]
+    fn quote_for_shell() {
+        assert_eq!(shell_quote("hunter2"), "'hunter2'");
+        assert_eq!(shell_quote("with space"), "'with space'");
+        assert_eq!(shell_quote("it's $HOME"), r"'it'\''s $HOME'");
+    }
+
+    #[test]
+    fn find_headless_checksum() -> Result<()> {
+        let checksums = "\
+0f6bf2d0a1b5cc7d4bf0fc4cc1c1bd1b7b5d7b1cf2a0d3c7e2b8f0e7a1e0b1c2  factorio_linux_2.0.28.tar.xz
+AB12CD34EF56AB12CD34EF56AB12CD34EF56AB12CD34EF56AB12CD34EF56AB12  factorio-headless_linux_2.0.28.tar.xz
+ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff  factorio-headless_linux_2.0.280.tar.xz
+";
+
+        assert_eq!(
+            find_checksum(checksums, "2.0.28")?,
+            "ab12cd34ef56ab12cd34ef56ab12cd34ef56ab12cd34ef56ab12cd34ef56ab12"
+        );
+        assert!(find_checksum(checksums, "2.0.30").is_err());
+
+        Ok(())
+    }
+}
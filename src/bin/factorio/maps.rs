@@ -0,0 +1,109 @@
+use std::{collections::HashMap, fs::create_dir_all, path::PathBuf};
+
+use utils::prelude::*;
+
+use crate::{Factorio, shell_quote};
+
+/// Make sure a save name can be safely used as a file name and on the command line.
+fn ensure_valid_save_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '-'));
+    if name.is_empty() || !valid_chars {
+        bail!("Invalid save name: {name}. Only letters, digits, '_' and '-' are allowed");
+    }
+
+    Ok(())
+}
+
+impl Factorio {
+    fn saves_dir(&self) -> PathBuf {
+        self.config.game_dir().join("saves")
+    }
+
+    /// The path of a save file by its name, e.g. `saves/winter_2025.zip`.
+    pub fn save_path(&self, name: &str) -> Result<PathBuf> {
+        ensure_valid_save_name(name)?;
+        Ok(self.saves_dir().join(format!("{name}.zip")))
+    }
+
+    /// Create a new map with the map generation settings from the default config dir.
+    ///
+    /// The `seed` is templated into the map generation settings. A random seed is used if none is
+    /// given.
+    pub fn new_map(&self, name: &str, seed: Option<u32>) -> Result<()> {
+        // Factorio locks its data directory, so the server must not be running.
+        if self.is_session_open()? {
+            bail!("The server must be stopped to create a new map");
+        }
+
+        let save_path = self.save_path(name)?;
+        if save_path.exists() {
+            bail!("There's already a save with the name {name} at {save_path:?}");
+        }
+        create_dir_all(self.saves_dir())?;
+
+        let mut secrets = HashMap::new();
+        secrets.insert(
+            "seed",
+            seed.map(|seed| seed.to_string())
+                .unwrap_or_else(|| "null".into()),
+        );
+
+        // Deploy the map settings
+        let config_dir = self.config.game_dir().join("config");
+        create_dir_all(&config_dir)?;
+        let map_gen_settings_path = config_dir.join("map-gen-settings.json");
+        copy_secret_file(
+            &self
+                .config
+                .default_config_dir()
+                .join("factorio-map-gen-settings.json"),
+            &map_gen_settings_path,
+            &secrets,
+        )
+        .wrap_err("Failed while copying map gen settings")?;
+
+        let map_settings_path = config_dir.join("map-settings.json");
+        copy_secret_file(
+            &self
+                .config
+                .default_config_dir()
+                .join("factorio-map-settings.json"),
+            &map_settings_path,
+            &secrets,
+        )
+        .wrap_err("Failed while copying map settings")?;
+
+        info!("Creating new map {save_path:?}");
+        cmd!(
+            "{}/bin/x64/factorio \
+        --create {} \
+        --map-gen-settings {} \
+        --map-settings {}",
+            self.config.game_dir_str(),
+            shell_quote(&save_path.to_string_lossy()),
+            map_gen_settings_path.to_string_lossy(),
+            map_settings_path.to_string_lossy()
+        )
+        .io_passthrough()
+        .run_success()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_names() {
+        assert!(ensure_valid_save_name("winter_2025").is_ok());
+        assert!(ensure_valid_save_name("space-age").is_ok());
+        assert!(ensure_valid_save_name("").is_err());
+        assert!(ensure_valid_save_name("../mods/evil").is_err());
+        assert!(ensure_valid_save_name("with space").is_err());
+        assert!(ensure_valid_save_name("$(reboot)").is_err());
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    collections::BTreeMap,
+    fs::{File, create_dir_all, read_dir, remove_file, rename, write},
+    path::PathBuf,
+};
+
+use serde_derive::{Deserialize, Serialize};
+use utils::{http, prelude::*};
+
+use crate::Factorio;
+
+/// Mods that ship with the game and thereby can't be downloaded from the mod portal.
+/// `base` is always enabled, the others are only enabled if they're declared in the config.
+const BUILTIN_MODS: [&str; 4] = ["base", "elevated-rails", "quality", "space-age"];
+
+/// The response of the mod portal's `/api/mods/{name}` endpoint.
+#[derive(Debug, Deserialize)]
+struct ModInfo {
+    releases: Vec<Release>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    download_url: String,
+    file_name: String,
+    version: String,
+    sha1: String,
+    info_json: ReleaseInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseInfo {
+    /// The major version of factorio the release is made for, e.g. `2.0`.
+    factorio_version: String,
+}
+
+/// The `info.json` of the base mod, which contains the version of the installed game.
+#[derive(Debug, Deserialize)]
+struct BaseInfo {
+    version: String,
+}
+
+/// The `mods/mod-list.json` file, which tells factorio which mods should be loaded.
+#[derive(Debug, Serialize)]
+struct ModList {
+    mods: Vec<ModListEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModListEntry {
+    name: String,
+    enabled: bool,
+}
+
+/// Parse a version such as `1.2.10` into something that can be compared.
+fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// The major version of a factorio version, e.g. `2.0` for `2.0.28`.
+fn major_version(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+/// Select the release that matches the declared version.
+///
+/// `latest` resolves to the newest release for the given major version of factorio.
+/// If the game isn't installed yet, the newest release is used.
+fn select_release(
+    releases: Vec<Release>,
+    version: &str,
+    factorio_version: Option<&str>,
+) -> Option<Release> {
+    if version != "latest" {
+        return releases
+            .into_iter()
+            .find(|release| release.version == version);
+    }
+
+    let major = factorio_version.map(major_version);
+    releases
+        .into_iter()
+        .filter(|release| {
+            major
+                .as_ref()
+                .is_none_or(|major| &release.info_json.factorio_version == major)
+        })
+        .max_by_key(|release| version_key(&release.version))
+}
+
+impl Factorio {
+    fn mods_dir(&self) -> PathBuf {
+        self.config.game_dir().join("mods")
+    }
+
+    /// Get the mods from the config that have to be downloaded from the mod portal.
+    fn declared_mods(&self) -> BTreeMap<&String, &String> {
+        self.config
+            .factorio
+            .mods
+            .iter()
+            .filter(|(name, _)| !BUILTIN_MODS.contains(&name.as_str()))
+            .collect()
+    }
+
+    /// The version of the installed game, read from the `info.json` of the base mod.
+    fn installed_factorio_version(&self) -> Result<Option<String>> {
+        let path = self.config.game_dir().join("data/base/info.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(&path).wrap_err(format!("Failed to open {path:?}"))?;
+        let info: BaseInfo =
+            serde_json::from_reader(file).wrap_err(format!("Failed to parse {path:?}"))?;
+        Ok(Some(info.version))
+    }
+
+    /// Get the release of a mod that matches the declared version.
+    fn find_release(
+        &self,
+        name: &str,
+        version: &str,
+        factorio_version: Option<&str>,
+    ) -> Result<Release> {
+        let url = format!(
+            "{}/api/mods/{}",
+            self.config.factorio.mod_portal_url,
+            http::encode_path_segment(name)
+        );
+        let info: ModInfo =
+            http::get_json(&url).wrap_err(format!("Failed to get info for mod {name}"))?;
+
+        select_release(info.releases, version, factorio_version).ok_or_else(|| match version {
+            "latest" => eyre!(
+                "Couldn't find a release of mod {name} for factorio {}",
+                factorio_version.unwrap_or_default()
+            ),
+            _ => eyre!("Couldn't find version {version} of mod {name}"),
+        })
+    }
+
+    /// Get the file names of all mod archives that're currently installed.
+    fn installed_mod_files(&self) -> Result<Vec<String>> {
+        let mods_dir = self.mods_dir();
+        if !mods_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for entry in read_dir(&mods_dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if name.ends_with(".zip") {
+                files.push(name);
+            }
+        }
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// Print the declared mods and the mod archives that're currently installed.
+    pub fn list_mods(&self) -> Result<()> {
+        println!("Declared mods:");
+        for (name, version) in &self.config.factorio.mods {
+            println!("  {name}: {version}");
+        }
+
+        println!("Installed mods:");
+        for file in self.installed_mod_files()? {
+            println!("  {file}");
+        }
+
+        Ok(())
+    }
+
+    /// Bring the mods directory in line with the mods declared in the config.
+    ///
+    /// 1. Download all missing mods from the mod portal.
+    /// 2. Remove all mod archives that aren't declared (anymore).
+    /// 3. Write the `mod-list.json`.
+    ///
+    /// If no mods are declared, the mods directory isn't touched at all.
+    pub fn sync_mods(&self) -> Result<()> {
+        if self.config.factorio.mods.is_empty() {
+            warn!("No mods are declared in the [factorio.mods] section, nothing to sync");
+            return Ok(());
+        }
+
+        let mods_dir = self.mods_dir();
+        create_dir_all(&mods_dir).wrap_err(format!("Failed to create mods dir {mods_dir:?}"))?;
+
+        let declared = self.declared_mods();
+        if !declared.is_empty()
+            && (self.config.factorio.username.is_empty() || self.config.factorio.token.is_empty())
+        {
+            bail!("A factorio.com username and token are needed to download mods");
+        }
+
+        let factorio_version = self.installed_factorio_version()?;
+        let installed = self.installed_mod_files()?;
+        let mut wanted_files = Vec::new();
+        for (name, version) in declared {
+            let release = self.find_release(name, version, factorio_version.as_deref())?;
+            wanted_files.push(release.file_name.clone());
+
+            if installed.contains(&release.file_name) {
+                debug!("Mod {} is already installed", release.file_name);
+                continue;
+            }
+
+            // Download to a temporary file first, so there're no half-downloaded mods lying
+            // around if anything goes wrong.
+            // The credentials are passed separately, so they don't end up in logs.
+            let url = format!(
+                "{}{}",
+                self.config.factorio.mod_portal_url, release.download_url
+            );
+            let credentials = [
+                ("username", self.config.factorio.username.as_str()),
+                ("token", self.config.factorio.token.as_str()),
+            ];
+            let dest = mods_dir.join(&release.file_name);
+            let temp_dest = mods_dir.join(format!("{}.part", release.file_name));
+            info!("Downloading mod {name} {}", release.version);
+            http::download_with_query(&url, &credentials, &temp_dest)
+                .wrap_err(format!("Failed to download mod {name}"))?;
+
+            let checksum = http::sha1_file(&temp_dest)?;
+            if checksum != release.sha1 {
+                remove_file(&temp_dest)?;
+                bail!(
+                    "Checksum mismatch for mod {name}. Expected {}, got {checksum}",
+                    release.sha1
+                );
+            }
+            rename(&temp_dest, &dest)?;
+        }
+
+        // Remove all mods that're no longer wanted.
+        for file in installed {
+            if !wanted_files.contains(&file) {
+                info!("Removing stale mod {file}");
+                remove_file(mods_dir.join(&file))?;
+            }
+        }
+
+        self.write_mod_list()
+    }
+
+    /// Write the `mod-list.json`, which enables all declared mods.
+    fn write_mod_list(&self) -> Result<()> {
+        let mut mods = vec![ModListEntry {
+            name: "base".into(),
+            enabled: true,
+        }];
+        for builtin in BUILTIN_MODS.iter().skip(1) {
+            mods.push(ModListEntry {
+                name: builtin.to_string(),
+                enabled: self.config.factorio.mods.contains_key(*builtin),
+            });
+        }
+        for name in self.declared_mods().keys() {
+            mods.push(ModListEntry {
+                name: name.to_string(),
+                enabled: true,
+            });
+        }
+
+        let path = self.mods_dir().join("mod-list.json");
+        let content = serde_json::to_string_pretty(&ModList { mods })?;
+        write(&path, content).wrap_err(format!("Failed to write {path:?}"))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn releases() -> Vec<Release> {
+        let response = r#"{
+            "name": "Krastorio2",
+            "releases": [
+                {
+                    "download_url": "/download/Krastorio2/1",
+                    "file_name": "Krastorio2_1.3.24.zip",
+                    "info_json": {"factorio_version": "1.1"},
+                    "released_at": "2024-01-01T00:00:00.000000Z",
+                    "sha1": "aaaa",
+                    "version": "1.3.24"
+                },
+                {
+                    "download_url": "/download/Krastorio2/2",
+                    "file_name": "Krastorio2_2.0.9.zip",
+                    "info_json": {"factorio_version": "2.0", "dependencies": ["base >= 2.0"]},
+                    "released_at": "2025-01-01T00:00:00.000000Z",
+                    "sha1": "bbbb",
+                    "version": "2.0.9"
+                },
+                {
+                    "download_url": "/download/Krastorio2/3",
+                    "file_name": "Krastorio2_2.0.10.zip",
+                    "info_json": {"factorio_version": "2.0"},
+                    "released_at": "2025-02-01T00:00:00.000000Z",
+                    "sha1": "cccc",
+                    "version": "2.0.10"
+                },
+                {
+                    "download_url": "/download/Krastorio2/4",
+                    "file_name": "Krastorio2_2.1.0.zip",
+                    "info_json": {"factorio_version": "2.1"},
+                    "released_at": "2025-03-01T00:00:00.000000Z",
+                    "sha1": "dddd",
+                    "version": "2.1.0"
+                }
+            ]
+        }"#;
+
+        serde_json::from_str::<ModInfo>(response).unwrap().releases
+    }
+
+    #[test]
+    fn versions() {
+        assert!(version_key("2.0.10") > version_key("2.0.9"));
+        assert!(version_key("10.0.0") > version_key("9.9.9"));
+        assert_eq!(major_version("2.0.28"), "2.0");
+        assert_eq!(major_version("1.1"), "1.1");
+    }
+
+    #[test]
+    fn select_exact_version() {
+        let release = select_release(releases(), "1.3.24", Some("2.0.28")).unwrap();
+        assert_eq!(release.file_name, "Krastorio2_1.3.24.zip");
+
+        assert!(select_release(releases(), "3.0.0", Some("2.0.28")).is_none());
+    }
+
+    #[test]
+    fn select_latest_for_installed_game() {
+        let release = select_release(releases(), "latest", Some("2.0.28")).unwrap();
+        assert_eq!(release.version, "2.0.10");
+        assert_eq!(release.sha1, "cccc");
+
+        let release = select_release(releases(), "latest", Some("1.1.110")).unwrap();
+        assert_eq!(release.version, "1.3.24");
+
+        assert!(select_release(releases(), "latest", Some("0.18.47")).is_none());
+    }
+
+    #[test]
+    fn select_latest_without_installed_game() {
+        let release = select_release(releases(), "latest", None).unwrap();
+        assert_eq!(release.version, "2.1.0");
+    }
+}
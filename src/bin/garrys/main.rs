@@ -0,0 +1,383 @@
+use std::{
+    collections::HashMap,
+    fs::{read_to_string, write},
+    path::PathBuf,
+};
+
+use clap::Parser;
+use utils::prelude::*;
+
+mod workshop;
+
+#[derive(Debug, Parser)]
+enum MapsCommand {
+    /// Show the map pool of a game mode and whether the maps are installed.
+    List {
+        #[clap(default_value = DEFAULT_MODE)]
+        mode: String,
+    },
+    /// Write the map pool of a game mode to the `mapcycle.txt` and `maplist.txt`.
+    Write {
+        #[clap(default_value = DEFAULT_MODE)]
+        mode: String,
+        /// Write the map cycle, even if some maps can't be found.
+        #[clap(long)]
+        force: bool,
+    },
+    /// Change the map of the running server.
+    Change { map: String },
+}
+
+#[derive(Debug, Parser)]
+enum WorkshopCommand {
+    /// Download the workshop collection of a game mode and mount it as local addons.
+    ///
+    /// Shows which items changed since the last sync.
+    Sync {
+        #[clap(default_value = DEFAULT_MODE)]
+        mode: String,
+    },
+}
+
+#[derive(Debug, Parser)]
+enum SubCommand {
+    Startup {
+        /// The game mode. Besides the modes from the config, ttt, prophunt and zombie are
+        /// available.
+        #[clap(default_value = DEFAULT_MODE)]
+        gamemode: String,
+    },
+    /// List all available game modes.
+    Modes,
+    /// Manage the workshop content of the game modes.
+    Workshop {
+        #[clap(subcommand)]
+        cmd: WorkshopCommand,
+    },
+    /// Manage the map pools and the current map.
+    Maps {
+        #[clap(subcommand)]
+        cmd: MapsCommand,
+    },
+    Shutdown {
+        /// Only shut the server down if no players are online.
+        #[clap(long)]
+        if_idle: bool,
+    },
+    /// Show whether the server is running, its map and its players.
+    Status,
+    /// Send a command to the server console and print its output.
+    ///
+    /// Output is only available if RCON is configured.
+    Command { command: String },
+    /// Restore the game files from the snapshot taken before the last update.
+    Rollback,
+    /// Check whether a new server build is available.
+    CheckUpdate,
+    Update {
+        /// Only update if a new server build is available.
+        #[clap(long)]
+        if_needed: bool,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "Garry's mod",
+    about = "A small binary to manage my Garry's mod server"
+)]
+struct CliArguments {
+    #[clap(subcommand)]
+    pub cmd: SubCommand,
+    /// The instance to manage. Instances are declared in the `[instances.garrys]` section of
+    /// the config. The default instance is used if none is given.
+    #[clap(long, global = true)]
+    pub instance: Option<String>,
+}
+
+const GAME_NAME: &str = "garrys";
+const DEFAULT_MODE: &str = "ttt";
+/// The file in the game dir that remembers the game mode the server has been started with.
+const MODE_FILE: &str = ".gamemode";
+const PORT: u16 = 27015;
+const CLIENT_PORT: u16 = 27005;
+
+fn main() -> Result<()> {
+    install_tracing()?;
+
+    // Parse commandline options.
+    let args = CliArguments::parse();
+    let mut server = Garrys::new(args.instance)?;
+
+    match args.cmd {
+        SubCommand::Startup { gamemode } => {
+            server.gamemode = gamemode;
+            server.startup()
+        }
+        SubCommand::Workshop { cmd } => match cmd {
+            WorkshopCommand::Sync { mode } => {
+                let collection = server
+                    .config
+                    .garrys
+                    .mode(&mode)?
+                    .workshop_collection
+                    .ok_or_else(|| eyre!("Game mode {mode} doesn't have a workshop collection"))?;
+                if !server.sync_workshop_addons(collection)? {
+                    warn!("The server has to download the collection by itself");
+                }
+                Ok(())
+            }
+        },
+        SubCommand::Modes => {
+            for (name, mode) in server.config.garrys.modes() {
+                println!("{name}: {} on {}", mode.gamemode, mode.map);
+            }
+            Ok(())
+        }
+        SubCommand::Maps { cmd } => match cmd {
+            MapsCommand::List { mode } => server.list_maps(&server.config.garrys.mode(&mode)?.maps),
+            MapsCommand::Write { mode, force } => {
+                server.write_map_cycle(&server.config.garrys.mode(&mode)?.maps, force)
+            }
+            MapsCommand::Change { map } => server.change_level(&map),
+        },
+        SubCommand::Shutdown { if_idle } => {
+            if if_idle {
+                server.shutdown_if_idle()
+            } else {
+                server.shutdown()
+            }
+        }
+        SubCommand::Status => server.status(),
+        SubCommand::Command { command } => {
+            if let Some(output) = server.send_command(&command)? {
+                println!("{output}");
+            }
+            Ok(())
+        }
+        SubCommand::Rollback => server.rollback(),
+        SubCommand::CheckUpdate => server.check_update(),
+        SubCommand::Update { if_needed } => {
+            if if_needed {
+                server.update_if_needed()
+            } else {
+                server.update()
+            }
+        }
+    }
+}
+
+struct Garrys {
+    config: Config,
+    /// The name of the game mode that's started.
+    /// Defaults to the mode the server has last been started with, so updates and rollbacks
+    /// restart the server in the same mode.
+    pub gamemode: String,
+}
+
+impl Garrys {
+    fn new(instance: Option<String>) -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.select_instance(instance)?;
+        let mut server = Self {
+            config,
+            gamemode: DEFAULT_MODE.to_string(),
+        };
+
+        let mode_file = server.mode_file();
+        if mode_file.exists() {
+            let gamemode = read_to_string(&mode_file)
+                .wrap_err(format!("Failed to read {mode_file:?}"))?
+                .trim()
+                .to_string();
+            if !gamemode.is_empty() {
+                server.gamemode = gamemode;
+            }
+        }
+
+        Ok(server)
+    }
+
+    fn mode_file(&self) -> PathBuf {
+        self.config.game_dir().join(MODE_FILE)
+    }
+}
+
+/// Quote a value for the command line, if it contains whitespace.
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{value}\"")
+    } else {
+        value.to_string()
+    }
+}
+
+impl TmuxServer for Garrys {}
+
+impl SteamServer for Garrys {
+    /// The Garry's mod server has the id 4020.
+    fn app_id(&self) -> u32 {
+        4020
+    }
+}
+
+impl SourceServer for Garrys {
+    fn game_folder(&self) -> &'static str {
+        "garrysmod"
+    }
+
+    /// Workshop items belong to Garry's mod itself.
+    fn workshop_app_id(&self) -> u32 {
+        4000
+    }
+}
+
+impl GameServer for Garrys {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    fn is_ready(&self) -> Result<bool> {
+        self.is_port_bound_by_session(self.config.port(PORT)?, Protocol::Udp)
+    }
+
+    fn query_client(&self) -> Result<Option<Box<dyn Query>>> {
+        Ok(Some(Box::new(A2sClient::new(format!(
+            "127.0.0.1:{}",
+            self.config.port(PORT)?
+        )))))
+    }
+
+    fn rcon_settings(&self) -> Result<Option<RconSettings>> {
+        self.config
+            .garrys
+            .rcon
+            .as_ref()
+            .map(|rcon| rcon.settings(&self.config))
+            .transpose()
+    }
+
+    /// Addons, server data and configs aren't part of the installation.
+    fn preserved_paths(&self) -> Vec<&'static str> {
+        vec![
+            MODE_FILE,
+            "garrysmod/addons",
+            "garrysmod/cfg",
+            "garrysmod/data",
+            "garrysmod/sv.db",
+            "steamapps/workshop",
+        ]
+    }
+
+    fn startup_inner(&self) -> Result<()> {
+        // Don't start the server if the session is already running.
+        self.ensure_session_not_open()?;
+        let mode = self.config.garrys.mode(&self.gamemode)?;
+        let mode_file = self.mode_file();
+        write(&mode_file, &self.gamemode).wrap_err(format!("Failed to write {mode_file:?}"))?;
+
+        // Pre-download the workshop collection, so the server doesn't have to do it on boot.
+        // Leftovers of previous syncs are removed, as they might belong to another game mode.
+        let workshop_synced = match mode.workshop_collection {
+            Some(collection) if self.config.garrys.workshop_sync => {
+                match self.sync_workshop_addons(collection) {
+                    Ok(synced) => synced,
+                    Err(error) => {
+                        warn!(
+                            "Workshop sync failed, the server downloads the collection: {error:?}"
+                        );
+                        self.remove_workshop_addons()?;
+                        false
+                    }
+                }
+            }
+            _ => {
+                self.remove_workshop_addons()?;
+                false
+            }
+        };
+
+        let game_dir = self.config.game_dir();
+        self.start_session(None)?;
+
+        // Remove the old compiled server config to avoid caching fuckery
+        let server_vdf = game_dir.join("garrysmod/cfg/server.vdf");
+        if server_vdf.exists() {
+            std::fs::remove_file(server_vdf)?;
+        }
+
+        // Load all secrets
+        let mut secrets = HashMap::new();
+        secrets.insert("password", self.config.password());
+        // An empty RCON password disables RCON.
+        let rcon_password = self
+            .rcon_settings()?
+            .map(|settings| settings.password)
+            .unwrap_or_default();
+        secrets.insert("rcon_password", rcon_password);
+
+        // Deploy the config file of the game mode and build the command.
+        if let Some(config_file) = &mode.config_file {
+            copy_secret_file(
+                &self.config.default_config_dir().join(config_file),
+                &game_dir.join("garrysmod/cfg/server.cfg"),
+                &secrets,
+            )
+            .wrap_err(format!("Failed to copy {} server config", self.gamemode))?;
+        }
+
+        // Workshop maps are only downloaded once the server has started.
+        if !mode.maps.is_empty() {
+            self.write_map_cycle(&mode.maps, true)?;
+        }
+
+        let mut server_command = format!(
+            "./srcds_run -game garrysmod -usercon -authkey $STEAM_WEB_API_KEY \
+            +gamemode {} +hostname {}",
+            mode.gamemode,
+            quote(&self.config.garrys.hostname)
+        );
+        if let Some(collection) = mode.workshop_collection
+            && !workshop_synced
+        {
+            server_command.push_str(&format!(" +host_workshop_collection {collection}"));
+        }
+        if let Some(max_players) = mode.max_players {
+            server_command.push_str(&format!(" +maxplayers {max_players}"));
+        }
+        for (convar, value) in &mode.convars {
+            server_command.push_str(&format!(" +{convar} {}", quote(value)));
+        }
+        server_command.push_str(&format!(
+            " -port {} +clientport {} +map {}",
+            self.config.port(PORT)?,
+            self.config.port(CLIENT_PORT)?,
+            self.config.world(&mode.map)
+        ));
+
+        let envs = map_macro::hash_map! {
+            "STEAM_WEB_API_KEY" => self.config.garrys.steam_web_api_key.clone()
+        };
+        self.send_input_newline_with_env(&server_command, envs)?;
+
+        Ok(())
+    }
+
+    fn update_inner(&self) -> Result<()> {
+        self.steamcmd_update()
+    }
+
+    fn shutdown_inner(&self) -> Result<()> {
+        // Exit if the server is not running.
+        self.ensure_session_is_open()?;
+
+        self.send_ctrl_c()?;
+        self.send_input_newline("exit")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    fs::{create_dir_all, read_dir, remove_file, write},
+    os::unix::fs::symlink,
+    path::PathBuf,
+};
+
+use utils::prelude::*;
+
+use crate::Garrys;
+
+/// The lua file that tells clients which workshop items they need to download.
+const WORKSHOP_LUA: &str = "garrysmod/lua/autorun/server/workshop_collection.lua";
+/// The prefix of the addons that're linked from the workshop content.
+const ADDON_PREFIX: &str = "workshop_";
+
+impl Garrys {
+    fn addons_dir(&self) -> PathBuf {
+        self.config.game_dir().join("garrysmod/addons")
+    }
+
+    /// Find the addon of a downloaded workshop item.
+    ///
+    /// Legacy items only ship a compressed `.bin` file, which can't be mounted as addon.
+    fn find_addon(&self, item: &WorkshopItem) -> Result<Option<PathBuf>> {
+        let item_dir = self.workshop_content_dir().join(&item.id);
+        if !item_dir.exists() {
+            return Ok(None);
+        }
+
+        for entry in read_dir(&item_dir).wrap_err(format!("Failed to read {item_dir:?}"))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "gma") {
+                return Ok(Some(path));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Download a workshop collection and mount its items as local addons.
+    ///
+    /// Clients are told to download the items via `resource.AddWorkshop`, so the server doesn't
+    /// need `+host_workshop_collection` anymore.
+    /// If any item can't be mounted, the addons are removed again and `false` is returned,
+    /// in which case the server needs to download the collection by itself.
+    pub fn sync_workshop_addons(&self, collection: u64) -> Result<bool> {
+        let (items, report) = self.sync_workshop(collection)?;
+        info!(
+            "{} - Workshop collection {collection}:\n{report}",
+            self.session_name()
+        );
+
+        self.remove_workshop_addons()?;
+        let addons_dir = self.addons_dir();
+        create_dir_all(&addons_dir).wrap_err(format!("Failed to create {addons_dir:?}"))?;
+
+        let mut lua = String::from("-- Generated by the garrys binary, changes are overwritten.\n");
+        for item in &items {
+            let Some(addon) = self.find_addon(item)? else {
+                warn!("Workshop item {item} can't be mounted as local addon");
+                self.remove_workshop_addons()?;
+                return Ok(false);
+            };
+
+            let link = addons_dir.join(format!("{ADDON_PREFIX}{}.gma", item.id));
+            symlink(&addon, &link).wrap_err(format!("Failed to link {addon:?} to {link:?}"))?;
+            lua.push_str(&format!("resource.AddWorkshop(\"{}\")\n", item.id));
+        }
+
+        let lua_path = self.config.game_dir().join(WORKSHOP_LUA);
+        if let Some(parent) = lua_path.parent() {
+            create_dir_all(parent)?;
+        }
+        write(&lua_path, lua).wrap_err(format!("Failed to write {lua_path:?}"))?;
+
+        Ok(true)
+    }
+
+    /// Remove the addons and the lua file of a previous workshop sync.
+    pub fn remove_workshop_addons(&self) -> Result<()> {
+        let addons_dir = self.addons_dir();
+        if addons_dir.exists() {
+            for entry in read_dir(&addons_dir).wrap_err(format!("Failed to read {addons_dir:?}"))? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with(ADDON_PREFIX)
+                    && name.ends_with(".gma")
+                    && entry.file_type()?.is_symlink()
+                {
+                    remove_file(entry.path())?;
+                }
+            }
+        }
+
+        let lua_path = self.config.game_dir().join(WORKSHOP_LUA);
+        if lua_path.exists() {
+            remove_file(&lua_path).wrap_err(format!("Failed to remove {lua_path:?}"))?;
+        }
+
+        Ok(())
+    }
+}
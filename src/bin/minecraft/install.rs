@@ -0,0 +1,358 @@
+use std::{
+    fs::{File, create_dir_all, remove_file, rename},
+    path::{Path, PathBuf},
+};
+
+use serde_derive::{Deserialize, Serialize};
+use utils::{config::Loader, http, prelude::*};
+
+use crate::Minecraft;
+
+/// Mojang's version manifest, which lists all released versions.
+#[derive(Debug, Deserialize)]
+struct VersionManifest {
+    latest: LatestVersions,
+    versions: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatestVersions {
+    release: String,
+    snapshot: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestEntry {
+    id: String,
+    /// The url of the version's metadata.
+    url: String,
+}
+
+impl VersionManifest {
+    /// Resolve `latest` and `snapshot` to the newest release or snapshot.
+    /// Any other version is returned as is.
+    fn resolve(&self, version: &str) -> String {
+        match version {
+            "latest" => self.latest.release.clone(),
+            "snapshot" => self.latest.snapshot.clone(),
+            _ => version.to_string(),
+        }
+    }
+
+    /// The url of the given version's metadata.
+    fn metadata_url(&self, version: &str) -> Result<&str> {
+        self.versions
+            .iter()
+            .find(|entry| entry.id == version)
+            .map(|entry| entry.url.as_str())
+            .ok_or_else(|| eyre!("Couldn't find minecraft version {version}"))
+    }
+}
+
+/// The metadata of a single version.
+#[derive(Debug, Deserialize)]
+struct VersionInfo {
+    downloads: Downloads,
+    #[serde(rename = "javaVersion")]
+    java_version: Option<JavaVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JavaVersion {
+    major_version: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Downloads {
+    server: Option<Download>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Download {
+    sha1: String,
+    url: String,
+}
+
+/// What's installed in an instance.
+/// This is written on every update, so the server can be started without any guesswork.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Installation {
+    /// The minecraft version.
+    pub version: String,
+    pub loader: Loader,
+    /// The version of the loader. Vanilla servers don't have one.
+    pub loader_version: Option<String>,
+    /// The java major version that's required by the minecraft version.
+    #[serde(default)]
+    pub java_version: Option<u32>,
+}
+
+impl std::fmt::Display for Installation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.loader, self.version)?;
+        if let Some(loader_version) = &self.loader_version {
+            write!(f, " ({loader_version})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Minecraft {
+    pub fn server_jar_path(&self) -> PathBuf {
+        self.config.game_dir().join("server.jar")
+    }
+
+    fn installation_path(&self) -> PathBuf {
+        self.config.game_dir().join("installation.json")
+    }
+
+    /// The current installation, if the instance has been installed by us.
+    pub fn installation(&self) -> Result<Option<Installation>> {
+        let path = self.installation_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(&path).wrap_err(format!("Failed to open {path:?}"))?;
+        let installation =
+            serde_json::from_reader(file).wrap_err(format!("Failed to parse {path:?}"))?;
+
+        Ok(Some(installation))
+    }
+
+    fn version_manifest(&self) -> Result<VersionManifest> {
+        http::get_json(&self.config.minecraft.manifest_url)
+            .wrap_err("Failed to get minecraft version manifest")
+    }
+
+    /// Resolve `latest` and `snapshot` to the newest release or snapshot.
+    pub fn resolve_version(&self, version: &str) -> Result<String> {
+        if version != "latest" && version != "snapshot" {
+            return Ok(version.to_string());
+        }
+
+        let resolved = self.version_manifest()?.resolve(version);
+        info!("Resolved {version} to version {resolved}");
+
+        Ok(resolved)
+    }
+
+    /// Determine what should be installed for the given minecraft version.
+    ///
+    /// The loader is taken from the instance's settings, falling back to the installed one.
+    /// If no loader version is configured, the newest one for the minecraft version is used.
+    pub fn resolve_installation(&self, version: &str) -> Result<Installation> {
+        let settings = self.instance_settings();
+        let loader = match settings.loader {
+            Some(loader) => loader,
+            None => self
+                .installation()?
+                .map(|installation| installation.loader)
+                .unwrap_or_default(),
+        };
+        let loader_version = match (loader, settings.loader_version) {
+            (Loader::Vanilla, _) => None,
+            (_, Some(loader_version)) => Some(loader_version),
+            (loader, None) => Some(self.latest_loader_version(loader, version)?),
+        };
+
+        let java_version = self
+            .version_info(version)?
+            .java_version
+            .map(|java| java.major_version);
+
+        Ok(Installation {
+            version: version.to_string(),
+            loader,
+            loader_version,
+            java_version,
+        })
+    }
+
+    /// Install the server files for the given installation.
+    pub fn install(&self, installation: &Installation) -> Result<()> {
+        let version = &installation.version;
+        let loader_version = || {
+            installation
+                .loader_version
+                .as_deref()
+                .ok_or_else(|| eyre!("No {} version specified", installation.loader))
+        };
+
+        self.config.create_game_dir()?;
+        match installation.loader {
+            Loader::Vanilla => self.install_server_jar(version)?,
+            Loader::Paper => self.install_paper(version, loader_version()?)?,
+            Loader::Fabric => self.install_fabric(version, loader_version()?)?,
+            Loader::Forge => self.install_forge(version, loader_version()?)?,
+            Loader::NeoForge => self.install_neoforge(loader_version()?)?,
+        }
+
+        let path = self.installation_path();
+        let file = File::create(&path).wrap_err(format!("Failed to create {path:?}"))?;
+        serde_json::to_writer_pretty(file, installation)
+            .wrap_err(format!("Failed to write {path:?}"))?;
+
+        self.accept_eula()
+    }
+
+    /// Get the metadata of a version from the version manifest.
+    fn version_info(&self, version: &str) -> Result<VersionInfo> {
+        let manifest = self.version_manifest()?;
+        let url = manifest.metadata_url(version)?;
+
+        http::get_json(url).wrap_err(format!("Failed to get metadata of version {version}"))
+    }
+
+    /// Install the vanilla server jar of the given version.
+    pub fn install_server_jar(&self, version: &str) -> Result<()> {
+        let download = self
+            .version_info(version)?
+            .downloads
+            .server
+            .ok_or_else(|| eyre!("Version {version} doesn't have a server download"))?;
+
+        self.download_verified(&download.url, &self.server_jar_path(), |path| {
+            http::sha1_file(path).map(|actual| (download.sha1.to_lowercase(), actual))
+        })
+    }
+
+    /// Download a file next to its destination and only swap it in once its checksum has been
+    /// verified. `checksums` returns the expected and the actual checksum of the download.
+    pub fn download_verified(
+        &self,
+        url: &str,
+        dest: &Path,
+        checksums: impl FnOnce(&Path) -> Result<(String, String)>,
+    ) -> Result<()> {
+        let download_path = dest.with_extension("download");
+        http::download(url, &download_path)?;
+
+        let (expected, actual) = checksums(&download_path)?;
+        if actual != expected {
+            remove_file(&download_path)?;
+            bail!("Checksum mismatch for {url}. Expected {expected}, got {actual}");
+        }
+        info!("Checksum of {download_path:?} is valid");
+
+        rename(&download_path, dest)
+            .wrap_err(format!("Failed to move {download_path:?} to {dest:?}"))?;
+
+        Ok(())
+    }
+
+    /// The command that starts the server.
+    ///
+    /// Instances that haven't been installed by us bring their own start script.
+    pub fn start_command(&self) -> Result<String> {
+        let Some(installation) = self.installation()? else {
+            return Ok("./ServerStart.sh".into());
+        };
+
+        if let Some(required) = installation.java_version {
+            self.check_java_version(required)?;
+        }
+
+        let launch_args = match installation.loader {
+            Loader::Vanilla | Loader::Paper => "-jar server.jar".to_string(),
+            Loader::Fabric => "-jar fabric-server-launch.jar".to_string(),
+            Loader::Forge | Loader::NeoForge => self.forge_launch_args(&installation)?,
+        };
+
+        let mut command = vec![self.java_binary()];
+        command.extend(self.jvm_args());
+        command.push(launch_args);
+        command.push("nogui".into());
+
+        Ok(command.join(" "))
+    }
+
+    /// The server refuses to start until the EULA has been accepted.
+    fn accept_eula(&self) -> Result<()> {
+        let path = self.config.game_dir().join("eula.txt");
+        let mut eula = Properties::read(&path)?;
+        if eula.get("eula") == Some("true") {
+            return Ok(());
+        }
+
+        info!("Accepting the minecraft EULA (https://aka.ms/MinecraftEULA)");
+        create_dir_all(self.config.game_dir())?;
+        eula.set("eula", "true");
+        eula.write(&path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "latest": {"release": "1.21.4", "snapshot": "25w02a"},
+        "versions": [
+            {"id": "25w02a", "type": "snapshot", "url": "https://example.com/25w02a.json"},
+            {"id": "1.21.4", "type": "release", "url": "https://example.com/1.21.4.json"},
+            {"id": "1.20.1", "type": "release", "url": "https://example.com/1.20.1.json"}
+        ]
+    }"#;
+
+    fn manifest() -> VersionManifest {
+        serde_json::from_str(MANIFEST).unwrap()
+    }
+
+    #[test]
+    fn resolve_version() {
+        let manifest = manifest();
+        assert_eq!(manifest.resolve("latest"), "1.21.4");
+        assert_eq!(manifest.resolve("snapshot"), "25w02a");
+        assert_eq!(manifest.resolve("1.20.1"), "1.20.1");
+    }
+
+    #[test]
+    fn metadata_url() {
+        let manifest = manifest();
+        assert_eq!(
+            manifest.metadata_url("1.20.1").unwrap(),
+            "https://example.com/1.20.1.json"
+        );
+        assert!(manifest.metadata_url("1.7.10").is_err());
+    }
+
+    #[test]
+    fn display_installation() {
+        let mut installation = Installation {
+            version: "1.21.4".to_string(),
+            loader: Loader::Fabric,
+            loader_version: Some("0.16.10".to_string()),
+            java_version: Some(21),
+        };
+        assert_eq!(installation.to_string(), "fabric 1.21.4 (0.16.10)");
+
+        installation.loader = Loader::Vanilla;
+        installation.loader_version = None;
+        assert_eq!(installation.to_string(), "vanilla 1.21.4");
+    }
+
+    #[test]
+    fn version_info() {
+        let info: VersionInfo = serde_json::from_str(
+            r#"{
+                "downloads": {
+                    "client": {"sha1": "ffff", "size": 1, "url": "https://example.com/client.jar"},
+                    "server": {"sha1": "ABCD", "size": 1, "url": "https://example.com/server.jar"}
+                },
+                "javaVersion": {"component": "java-runtime-delta", "majorVersion": 21}
+            }"#,
+        )
+        .unwrap();
+        let server = info.downloads.server.unwrap();
+        assert_eq!(server.sha1, "ABCD");
+        assert_eq!(server.url, "https://example.com/server.jar");
+        assert_eq!(info.java_version.unwrap().major_version, 21);
+
+        // Old versions have neither a server download nor a java version.
+        let info: VersionInfo = serde_json::from_str(r#"{"downloads": {}}"#).unwrap();
+        assert!(info.downloads.server.is_none());
+        assert!(info.java_version.is_none());
+    }
+}
@@ -0,0 +1,180 @@
+use std::{collections::BTreeMap, fs::remove_dir_all};
+
+use utils::{config::Loader, prelude::*};
+
+use crate::{
+    DEFAULT_PORT,
+    GAME_NAME,
+    Minecraft,
+    RCON_PORT_OFFSET,
+    install::Installation,
+    settings::server_port,
+};
+
+/// Make sure an instance name can be used as directory and session name.
+fn ensure_valid_instance_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+    {
+        bail!("Invalid instance name: {name}");
+    }
+
+    Ok(())
+}
+
+/// The ports that're used by an instance on the given server port.
+fn used_ports(port: u16) -> [u16; 2] {
+    [port, port.saturating_add(RCON_PORT_OFFSET)]
+}
+
+impl Minecraft {
+    /// The server ports of all existing instances, by instance name.
+    fn instance_ports(&self) -> Result<BTreeMap<String, u16>> {
+        let mut ports = BTreeMap::new();
+        for instance in self.instances()? {
+            let path = self
+                .config
+                .game_root()
+                .join(GAME_NAME)
+                .join(&instance)
+                .join("server.properties");
+            let port = server_port(&Properties::read(&path)?)
+                .wrap_err(format!("Failed to read the port of {path:?}"))?;
+            ports.insert(instance, port);
+        }
+
+        Ok(ports)
+    }
+
+    /// Print all instances with their installation, port and whether they're running.
+    pub fn list_instances(&mut self) -> Result<()> {
+        for (instance, port) in self.instance_ports()? {
+            self.set_instance(instance.clone());
+
+            let installed = match self.installation()? {
+                Some(installation) => installation.to_string(),
+                None => "custom".to_string(),
+            };
+
+            let state = if !self.is_running()? {
+                "stopped".to_string()
+            } else {
+                match self.query_client()?.map(|client| client.query()) {
+                    Some(Ok(info)) => {
+                        format!("running, {}/{} players", info.players, info.max_players)
+                    }
+                    _ => "running".to_string(),
+                }
+            };
+
+            println!("{instance}: {installed}, port {port}, {state}");
+        }
+
+        Ok(())
+    }
+
+    /// Create and install a new instance.
+    ///
+    /// If no port is given, the instance's declared `server-port` or the first port that doesn't
+    /// collide with any other instance is used. The RCON port is considered as well.
+    /// A given port must match the declared `server-port`, if there is one.
+    pub fn create_instance(
+        &mut self,
+        name: String,
+        version: &str,
+        loader: Option<Loader>,
+        port: Option<u16>,
+    ) -> Result<()> {
+        ensure_valid_instance_name(&name)?;
+        let ports = self.instance_ports()?;
+        if ports.contains_key(&name) {
+            bail!("Instance {name} already exists");
+        }
+
+        self.set_instance(name.clone());
+        let settings = self.instance_settings();
+        if let Some(loader) = loader {
+            match settings.loader {
+                Some(configured) if configured != loader => {
+                    bail!("Instance {name} is configured to use {configured} in the config")
+                }
+                // The installed loader is kept on updates, so it doesn't need to be configured.
+                _ => {
+                    self.config
+                        .minecraft
+                        .instances
+                        .entry(name.clone())
+                        .or_default()
+                        .loader = Some(loader)
+                }
+            }
+        }
+
+        let colliding = |port: u16| {
+            ports
+                .iter()
+                .find(|(_, other)| {
+                    used_ports(**other)
+                        .iter()
+                        .any(|other| used_ports(port).contains(other))
+                })
+                .map(|(instance, _)| instance)
+        };
+        // The declared port is written to the server.properties on every startup, so it must win.
+        let declared_port = self.declared_port()?;
+        if let (Some(port), Some(declared)) = (port, declared_port)
+            && port != declared
+        {
+            bail!(
+                "Instance {name} declares the server-port {declared} in the config, which conflicts with port {port}"
+            );
+        }
+        let port = match port.or(declared_port) {
+            Some(port) => {
+                if let Some(instance) = colliding(port) {
+                    bail!("Port {port} collides with the ports of instance {instance}");
+                }
+                port
+            }
+            None => (DEFAULT_PORT..u16::MAX - RCON_PORT_OFFSET)
+                .find(|port| colliding(*port).is_none())
+                .ok_or_else(|| eyre!("Couldn't find a free port"))?,
+        };
+
+        let version = self.resolve_version(version)?;
+        let installation = self.resolve_installation(&version)?;
+        info!("Creating instance {name} with {installation} on port {port}");
+
+        let game_dir = self.config.create_game_dir()?;
+        if let Err(error) = self.lay_out_instance(port, &installation) {
+            // Don't leave a broken instance behind, so the creation can simply be retried.
+            remove_dir_all(&game_dir).wrap_err(format!("Failed to remove {game_dir:?}"))?;
+            return Err(error);
+        }
+
+        info!("Created instance {name}, start it via `minecraft startup {name}`");
+
+        Ok(())
+    }
+
+    /// Write the port into the `server.properties` of a new instance and install it.
+    fn lay_out_instance(&self, port: u16, installation: &Installation) -> Result<()> {
+        let path = self.server_properties_path();
+        let mut properties = Properties::read(&path)?;
+        properties.set("server-port", &port.to_string());
+        properties.write(&path)?;
+
+        self.install(installation)?;
+        self.deploy_server_properties()?;
+
+        // Only the chosen port has been checked for collisions.
+        let final_port = server_port(&Properties::read(&path)?)?;
+        if final_port != port {
+            bail!("The server.properties ended up with port {final_port} instead of {port}");
+        }
+
+        self.write_player_lists()
+    }
+}
@@ -0,0 +1,101 @@
+use utils::prelude::*;
+
+use crate::Minecraft;
+
+/// Aikar's G1GC flags, which reduce GC pauses of minecraft servers.
+/// See https://docs.papermc.io/paper/aikars-flags
+const AIKAR_FLAGS: &[&str] = &[
+    "-XX:+UseG1GC",
+    "-XX:+ParallelRefProcEnabled",
+    "-XX:MaxGCPauseMillis=200",
+    "-XX:+UnlockExperimentalVMOptions",
+    "-XX:+DisableExplicitGC",
+    "-XX:+AlwaysPreTouch",
+    "-XX:G1NewSizePercent=30",
+    "-XX:G1MaxNewSizePercent=40",
+    "-XX:G1HeapRegionSize=8M",
+    "-XX:G1ReservePercent=20",
+    "-XX:G1HeapWastePercent=5",
+    "-XX:G1MixedGCCountTarget=4",
+    "-XX:InitiatingHeapOccupancyPercent=15",
+    "-XX:G1MixedGCLiveThresholdPercent=90",
+    "-XX:G1RSetUpdatingPauseTimePercent=5",
+    "-XX:SurvivorRatio=32",
+    "-XX:+PerfDisableSharedMem",
+    "-XX:MaxTenuringThreshold=1",
+    "-Dusing.aikars.flags=https://mcflags.emc.gs",
+    "-Daikars.new.flags=true",
+];
+
+impl Minecraft {
+    /// The java binary of the configured JDK or the one from the `PATH`.
+    pub fn java_binary(&self) -> String {
+        match self.instance_settings().java_home {
+            Some(java_home) => java_home.join("bin/java").to_string_lossy().to_string(),
+            None => "java".into(),
+        }
+    }
+
+    /// The JVM arguments from the instance's settings.
+    pub fn jvm_args(&self) -> Vec<String> {
+        let settings = self.instance_settings();
+
+        let mut args = Vec::new();
+        if let Some(min_memory) = &settings.min_memory {
+            args.push(format!("-Xms{min_memory}"));
+        }
+        if let Some(max_memory) = &settings.max_memory {
+            args.push(format!("-Xmx{max_memory}"));
+        }
+        if settings.aikar_flags {
+            args.extend(AIKAR_FLAGS.iter().map(|flag| flag.to_string()));
+        }
+        args.extend(settings.jvm_args);
+
+        args
+    }
+
+    /// Get the major version of the configured java binary.
+    pub fn java_version(&self) -> Result<u32> {
+        let java = self.java_binary();
+        let output = cmd!("{} -version", java)
+            .run_success()
+            .wrap_err(format!("Failed to get the version of {java}"))?;
+
+        // The version is printed to stderr, e.g. `openjdk version "21.0.5" 2024-10-15`.
+        // Java 8 and older use the `1.x` scheme, e.g. `java version "1.8.0_401"`.
+        let output = String::from_utf8_lossy(&output.stderr);
+        let version = output
+            .split('"')
+            .nth(1)
+            .ok_or_else(|| eyre!("Couldn't find version in output of {java} -version"))?;
+        let version = version.strip_prefix("1.").unwrap_or(version);
+        let major = version
+            .split(['.', '_', '-', '+'])
+            .next()
+            .and_then(|major| major.parse().ok())
+            .ok_or_else(|| eyre!("Couldn't parse java version {version}"))?;
+
+        Ok(major)
+    }
+
+    /// Make sure the configured java version can run the server.
+    ///
+    /// Older java versions can't run the server at all.
+    /// Newer versions usually work, but old modded servers are known to break on them.
+    pub fn check_java_version(&self, required: u32) -> Result<()> {
+        let major = self.java_version()?;
+        if major < required {
+            bail!(
+                "The server requires java {required}, but {} is java {major}. \
+                Set `java_home` in the instance's settings to use another JDK.",
+                self.java_binary()
+            );
+        }
+        if major > required {
+            warn!("The server is built for java {required}, but runs on java {major}");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::{collections::HashMap, fs::remove_file, path::PathBuf};
+
+use serde_derive::Deserialize;
+use utils::{config::Loader, http, prelude::*};
+
+use crate::{Minecraft, install::Installation};
+
+/// A single build of PaperMC's download API.
+#[derive(Debug, Deserialize)]
+struct PaperBuild {
+    id: u32,
+    channel: String,
+    downloads: HashMap<String, PaperDownload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperDownload {
+    checksums: PaperChecksums,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperChecksums {
+    sha256: String,
+}
+
+/// An entry of Fabric's loader versions for a specific minecraft version.
+#[derive(Debug, Deserialize)]
+struct FabricLoaderEntry {
+    loader: FabricVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricVersion {
+    version: String,
+    stable: bool,
+}
+
+/// A version of Fabric's installer, which is published on Fabric's maven.
+#[derive(Debug, Deserialize)]
+struct FabricInstaller {
+    url: String,
+    stable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForgePromotions {
+    promos: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MavenVersions {
+    versions: Vec<String>,
+}
+
+impl Minecraft {
+    /// Get the newest loader version that's available for the given minecraft version.
+    pub fn latest_loader_version(&self, loader: Loader, version: &str) -> Result<String> {
+        let latest = match loader {
+            Loader::Vanilla => bail!("Vanilla servers don't have a loader"),
+            Loader::Paper => self.latest_paper_build(version)?.id.to_string(),
+            Loader::Fabric => self.latest_fabric_loader(version)?,
+            Loader::Forge => self.latest_forge(version)?,
+            Loader::NeoForge => self.latest_neoforge(version)?,
+        };
+        info!("Using {loader} version {latest} for minecraft {version}");
+
+        Ok(latest)
+    }
+
+    fn paper_builds_url(&self, version: &str) -> String {
+        format!(
+            "{}/v3/projects/paper/versions/{version}/builds",
+            self.config.minecraft.paper_url
+        )
+    }
+
+    /// Get the newest stable Paper build.
+    /// Paper only marks builds as stable once a minecraft version has matured, so the newest
+    /// build of any channel is used if there isn't any stable one yet.
+    fn latest_paper_build(&self, version: &str) -> Result<PaperBuild> {
+        let builds: Vec<PaperBuild> = http::get_json(&self.paper_builds_url(version))
+            .wrap_err(format!("Failed to get Paper builds for {version}"))?;
+
+        let (stable, unstable): (Vec<_>, Vec<_>) = builds
+            .into_iter()
+            .partition(|build| build.channel == "STABLE");
+        if let Some(build) = stable.into_iter().max_by_key(|build| build.id) {
+            return Ok(build);
+        }
+
+        let build = unstable
+            .into_iter()
+            .max_by_key(|build| build.id)
+            .ok_or_else(|| eyre!("There are no Paper builds for {version}"))?;
+        warn!(
+            "There's no stable Paper build for {version}, using {} build {}",
+            build.channel, build.id
+        );
+
+        Ok(build)
+    }
+
+    pub fn install_paper(&self, version: &str, build: &str) -> Result<()> {
+        let url = format!("{}/{build}", self.paper_builds_url(version));
+        let build: PaperBuild =
+            http::get_json(&url).wrap_err(format!("Failed to get Paper build {build}"))?;
+        let download = build
+            .downloads
+            .get("server:default")
+            .ok_or_else(|| eyre!("Paper build {} doesn't have a server download", build.id))?;
+
+        self.download_verified(&download.url, &self.server_jar_path(), |path| {
+            http::sha256_file(path).map(|actual| (download.checksums.sha256.to_lowercase(), actual))
+        })
+    }
+
+    /// Get the newest stable Fabric loader.
+    fn latest_fabric_loader(&self, version: &str) -> Result<String> {
+        let url = format!(
+            "{}/v2/versions/loader/{version}",
+            self.config.minecraft.fabric_url
+        );
+        let loaders: Vec<FabricLoaderEntry> =
+            http::get_json(&url).wrap_err(format!("Failed to get Fabric loaders for {version}"))?;
+
+        loaders
+            .into_iter()
+            .map(|entry| entry.loader)
+            .find(|loader| loader.stable)
+            .map(|loader| loader.version)
+            .ok_or_else(|| eyre!("There's no stable Fabric loader for {version}"))
+    }
+
+    /// Install the vanilla server and Fabric's server launcher, which loads the vanilla jar.
+    ///
+    /// Fabric doesn't publish checksums for the launchers of its meta API, as they're generated
+    /// on the fly. Hence, the launcher is generated by Fabric's installer instead, which is
+    /// verified like the Forge installers.
+    pub fn install_fabric(&self, version: &str, loader: &str) -> Result<()> {
+        self.install_server_jar(version)?;
+
+        let fabric_url = &self.config.minecraft.fabric_url;
+        let installers: Vec<FabricInstaller> =
+            http::get_json(&format!("{fabric_url}/v2/versions/installer"))
+                .wrap_err("Failed to get Fabric installers")?;
+        let installer = installers
+            .into_iter()
+            .find(|installer| installer.stable)
+            .ok_or_else(|| eyre!("There's no stable Fabric installer"))?;
+
+        // The vanilla server has already been installed, so the installer only has to write the
+        // launcher and download the loader's libraries.
+        self.run_installer(
+            &installer.url,
+            &format!("server -mcversion {version} -loader {loader}"),
+        )
+    }
+
+    /// Get the recommended Forge version or the newest one, if none is recommended yet.
+    fn latest_forge(&self, version: &str) -> Result<String> {
+        let url = format!(
+            "{}/net/minecraftforge/forge/promotions_slim.json",
+            self.config.minecraft.forge_files_url
+        );
+        let mut promotions: ForgePromotions =
+            http::get_json(&url).wrap_err("Failed to get Forge promotions")?;
+
+        promotions
+            .promos
+            .remove(&format!("{version}-recommended"))
+            .or_else(|| promotions.promos.remove(&format!("{version}-latest")))
+            .ok_or_else(|| eyre!("There's no Forge version for {version}"))
+    }
+
+    pub fn install_forge(&self, version: &str, forge: &str) -> Result<()> {
+        let artifact = format!("{version}-{forge}");
+        let url = format!(
+            "{}/net/minecraftforge/forge/{artifact}/forge-{artifact}-installer.jar",
+            self.config.minecraft.forge_url
+        );
+        self.run_installer(&url, "--installServer")
+    }
+
+    /// Get the newest NeoForge version for a minecraft version.
+    ///
+    /// NeoForge versions are derived from the minecraft version,
+    /// e.g. `21.1.x` is built for minecraft `1.21.1` and `21.0.x` for minecraft `1.21`.
+    fn latest_neoforge(&self, version: &str) -> Result<String> {
+        let mut parts = version.split('.').skip(1);
+        let major = parts
+            .next()
+            .ok_or_else(|| eyre!("Can't derive NeoForge version from {version}"))?;
+        let minor = parts.next().unwrap_or("0");
+        let prefix = format!("{major}.{minor}.");
+
+        let url = format!(
+            "{}/api/maven/versions/releases/net/neoforged/neoforge",
+            self.config.minecraft.neoforge_url
+        );
+        let versions: MavenVersions =
+            http::get_json(&url).wrap_err("Failed to get NeoForge versions")?;
+
+        // Versions are sorted from oldest to newest. Beta versions are only used as a fallback.
+        let matching: Vec<String> = versions
+            .versions
+            .into_iter()
+            .filter(|neoforge| neoforge.starts_with(&prefix))
+            .collect();
+        matching
+            .iter()
+            .rev()
+            .find(|neoforge| !neoforge.contains("beta"))
+            .or_else(|| matching.last())
+            .cloned()
+            .ok_or_else(|| eyre!("There's no NeoForge version for {version}"))
+    }
+
+    pub fn install_neoforge(&self, neoforge: &str) -> Result<()> {
+        let url = format!(
+            "{}/releases/net/neoforged/neoforge/{neoforge}/neoforge-{neoforge}-installer.jar",
+            self.config.minecraft.neoforge_url
+        );
+        self.run_installer(&url, "--installServer")
+    }
+
+    /// Download a Fabric, Forge or NeoForge installer and install the server with it.
+    ///
+    /// The installer downloads the libraries by itself.
+    /// The installers are verified with the checksum that's published next to them.
+    fn run_installer(&self, url: &str, args: &str) -> Result<()> {
+        let game_dir = self.config.game_dir();
+        let installer_path = game_dir.join("installer.jar");
+        let expected = http::get_text(&format!("{url}.sha1"))?;
+        self.download_verified(url, &installer_path, |path| {
+            http::sha1_file(path).map(|actual| (expected.trim().to_lowercase(), actual))
+        })?;
+
+        info!("Running installer {url}");
+        let result = cmd!("{} -jar installer.jar {args}", self.java_binary())
+            .cwd(game_dir.clone())
+            .io_passthrough()
+            .run_success();
+
+        remove_file(&installer_path)?;
+        let log_path = game_dir.join("installer.jar.log");
+        if log_path.exists() {
+            remove_file(&log_path)?;
+        }
+        result?;
+
+        Ok(())
+    }
+
+    /// The java arguments to launch a Forge or NeoForge server.
+    ///
+    /// Modern installers write the server's classpath and arguments to an args file.
+    /// Older Forge versions install a runnable jar instead.
+    pub fn forge_launch_args(&self, installation: &Installation) -> Result<String> {
+        let loader_version = installation
+            .loader_version
+            .as_deref()
+            .ok_or_else(|| eyre!("No {} version installed", installation.loader))?;
+
+        let (args_dir, jar) = match installation.loader {
+            Loader::NeoForge => (
+                PathBuf::from(format!("libraries/net/neoforged/neoforge/{loader_version}")),
+                None,
+            ),
+            _ => {
+                let artifact = format!("{}-{loader_version}", installation.version);
+                (
+                    PathBuf::from(format!("libraries/net/minecraftforge/forge/{artifact}")),
+                    Some(format!("forge-{artifact}.jar")),
+                )
+            }
+        };
+
+        let args_file = args_dir.join("unix_args.txt");
+        if self.config.game_dir().join(&args_file).exists() {
+            return Ok(format!("@{}", args_file.to_string_lossy()));
+        }
+
+        match jar {
+            Some(jar) if self.config.game_dir().join(&jar).exists() => Ok(format!("-jar {jar}")),
+            _ => bail!("Couldn't find the launch arguments of {args_file:?}"),
+        }
+    }
+}
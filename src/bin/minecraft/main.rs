@@ -0,0 +1,428 @@
+use std::{
+    fs::read_dir,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use utils::{
+    config::{Loader, MinecraftInstance},
+    prelude::*,
+};
+
+mod install;
+mod instances;
+mod java;
+mod loaders;
+mod settings;
+
+use install::Installation;
+use settings::server_port;
+
+#[derive(Debug, Parser)]
+enum InstancesCommand {
+    /// List all instances with their installation, port and status.
+    List,
+    /// Create and install a new instance.
+    Create {
+        name: String,
+        /// The version to install.
+        /// `latest` and `snapshot` resolve to the newest release or snapshot.
+        #[clap(long, default_value = "latest")]
+        version: String,
+        /// The loader of the instance. Defaults to the configured loader or vanilla.
+        #[clap(long)]
+        loader: Option<Loader>,
+        /// The server port. Defaults to the first port that's not used by another instance.
+        #[clap(long)]
+        port: Option<u16>,
+    },
+}
+
+#[derive(Debug, Parser)]
+enum SubCommand {
+    Startup {
+        instance: String,
+    },
+    Shutdown {
+        instance: String,
+        /// Only shut the instance down if no players are online.
+        #[clap(long)]
+        if_idle: bool,
+    },
+    Backup {
+        instance: String,
+    },
+    /// Install or update the server of an instance.
+    ///
+    /// The loader (vanilla, paper, fabric, forge or neoforge) is configured per instance.
+    /// The EULA is accepted on the first install.
+    Update {
+        instance: String,
+        /// The version to install.
+        /// `latest` and `snapshot` resolve to the newest release or snapshot.
+        #[clap(default_value = "latest")]
+        version: String,
+    },
+    /// Restore an instance from the snapshot taken before the last update.
+    Rollback {
+        instance: String,
+    },
+    /// List all players that're currently online.
+    Players {
+        instance: String,
+    },
+    /// Show the version, MOTD and players of an instance.
+    ///
+    /// Shows the status of all instances if none is given.
+    Status {
+        instance: Option<String>,
+    },
+    /// Apply the properties and player lists from the instance's settings.
+    ///
+    /// Player lists are updated live on a running server.
+    /// Changed properties only take effect after a restart.
+    ///
+    /// Declared players are only ever added. Players that are removed from the config stay on
+    /// the lists until they're removed manually, e.g. via `deop` or `whitelist remove`.
+    Sync {
+        instance: String,
+    },
+    /// Manage the instances.
+    Instances {
+        #[clap(subcommand)]
+        cmd: InstancesCommand,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "Minecraft",
+    about = "A small binary to manage my Minecraft server"
+)]
+struct CliArguments {
+    #[clap(subcommand)]
+    pub cmd: SubCommand,
+}
+
+const GAME_NAME: &str = "minecraft";
+const DEFAULT_PORT: u16 = 25565;
+const DEFAULT_RCON_PORT: u16 = 25575;
+/// The RCON port of an instance is derived from its server port, like Minecraft's defaults.
+const RCON_PORT_OFFSET: u16 = 10;
+/// How long saving the world may take before a backup is aborted.
+const SAVE_TIMEOUT: Duration = Duration::from_secs(600);
+
+fn main() -> Result<()> {
+    install_tracing()?;
+
+    // Parse commandline options.
+    let args = CliArguments::parse();
+    let mut server = Minecraft::new()?;
+
+    match args.cmd {
+        SubCommand::Startup { instance } => {
+            server.set_instance(instance);
+            server.startup()?;
+            server.wait_until_ready()
+        }
+        SubCommand::Shutdown { instance, if_idle } => {
+            server.set_instance(instance);
+            if if_idle {
+                server.shutdown_if_idle()
+            } else {
+                server.shutdown()
+            }
+        }
+        SubCommand::Backup { instance } => {
+            server.set_instance(instance);
+            server.backup()
+        }
+        SubCommand::Update { instance, version } => {
+            server.set_instance(instance);
+            let version = server.resolve_version(&version)?;
+            let installation = server.resolve_installation(&version)?;
+            if server.installation()?.as_ref() == Some(&installation) {
+                info!("{installation} is already installed");
+                return Ok(());
+            }
+            server.installation = Some(installation);
+            server.update()
+        }
+        SubCommand::Rollback { instance } => {
+            server.set_instance(instance);
+            server.rollback()
+        }
+        SubCommand::Players { instance } => {
+            server.set_instance(instance);
+            for player in server.online_players()? {
+                println!("{player}");
+            }
+            Ok(())
+        }
+        SubCommand::Status { instance } => {
+            let instances = match instance {
+                Some(instance) => vec![instance],
+                None => server.instances()?,
+            };
+            // A broken instance shouldn't hide the status of the others.
+            let mut failed = 0;
+            for instance in instances {
+                server.set_instance(instance);
+                if let Err(error) = server.status() {
+                    error!("{error:?}");
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                bail!("Failed to get the status of {failed} instance(s)");
+            }
+            Ok(())
+        }
+        SubCommand::Sync { instance } => {
+            server.set_instance(instance);
+            server.sync_settings()
+        }
+        SubCommand::Instances { cmd } => match cmd {
+            InstancesCommand::List => server.list_instances(),
+            InstancesCommand::Create {
+                name,
+                version,
+                loader,
+                port,
+            } => server.create_instance(name, &version, loader, port),
+        },
+    }
+}
+
+struct Minecraft {
+    config: Config,
+    /// What to install during an update.
+    installation: Option<Installation>,
+}
+
+impl Minecraft {
+    fn new() -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.nested_instances = true;
+        Ok(Self {
+            config,
+            installation: None,
+        })
+    }
+
+    fn set_instance(&mut self, instance: String) {
+        self.config.instance = Some(instance);
+    }
+
+    /// The settings of the selected instance from the config.
+    fn instance_settings(&self) -> MinecraftInstance {
+        let instance = self.config.instance.as_deref().unwrap_or_default();
+        self.config.minecraft.instance(instance)
+    }
+
+    /// The names of all instances, i.e. all directories in the minecraft game directory.
+    fn instances(&self) -> Result<Vec<String>> {
+        let dir = self.config.game_root().join(GAME_NAME);
+        let mut instances = Vec::new();
+        if !dir.exists() {
+            return Ok(instances);
+        }
+        for entry in read_dir(&dir).wrap_err(format!("Failed to read {dir:?}"))? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                instances.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        instances.sort();
+
+        Ok(instances)
+    }
+
+    fn server_properties_path(&self) -> PathBuf {
+        self.config.game_dir().join("server.properties")
+    }
+
+    /// Get the names of all players that're currently online.
+    fn online_players(&self) -> Result<Vec<String>> {
+        let output = self
+            .send_command("list")?
+            .ok_or_else(|| eyre!("RCON isn't enabled for {}", self.session_name()))?;
+
+        // The output looks like this:
+        // `There are 2 of a max of 20 players online: Alice, Bob`
+        let players = output
+            .split_once(':')
+            .map(|(_, players)| players)
+            .unwrap_or_default()
+            .split(',')
+            .map(|player| player.trim().to_string())
+            .filter(|player| !player.is_empty())
+            .collect();
+
+        Ok(players)
+    }
+
+    /// Wait for the server to release its port after a `stop` command.
+    fn wait_until_stopped(&self, timeout: Duration) -> Result<()> {
+        let port = server_port(&Properties::read(&self.server_properties_path())?)?;
+        let start = Instant::now();
+        while is_port_bound(port, Protocol::Tcp)? {
+            if start.elapsed() > timeout {
+                warn!(
+                    "{} - Server didn't stop within {} seconds",
+                    self.session_name(),
+                    timeout.as_secs()
+                );
+                break;
+            }
+            sleep_seconds(1);
+        }
+
+        Ok(())
+    }
+}
+
+impl TmuxServer for Minecraft {}
+
+impl GameServer for Minecraft {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    /// Minecraft only answers status requests once the world has been loaded.
+    fn is_ready(&self) -> Result<bool> {
+        match self.query_client()? {
+            Some(client) => Ok(client.query().is_ok()),
+            None => Ok(false),
+        }
+    }
+
+    fn query_client(&self) -> Result<Option<Box<dyn Query>>> {
+        let port = server_port(&Properties::read(&self.server_properties_path())?)?;
+        Ok(Some(Box::new(MinecraftClient::new("127.0.0.1", port))))
+    }
+
+    /// RCON is used if it's enabled in the instance's `server.properties`.
+    fn rcon_settings(&self) -> Result<Option<RconSettings>> {
+        let properties = Properties::read(&self.server_properties_path())?;
+        if properties.get("enable-rcon") != Some("true") {
+            return Ok(None);
+        }
+
+        let port = properties
+            .get("rcon.port")
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_RCON_PORT);
+        let Some(password) = properties.get("rcon.password") else {
+            return Ok(None);
+        };
+        if password.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(RconSettings {
+            address: format!("127.0.0.1:{port}"),
+            password: password.to_string(),
+        }))
+    }
+
+    /// The worlds, properties and player lists of the instance.
+    ///
+    /// Since new versions upgrade the worlds, a backup is created before every update.
+    fn preserved_paths(&self) -> Vec<&'static str> {
+        vec![
+            "world",
+            "world_nether",
+            "world_the_end",
+            "server.properties",
+            "ops.json",
+            "whitelist.json",
+            "banned-players.json",
+            "banned-ips.json",
+            "usercache.json",
+        ]
+    }
+
+    fn startup_inner(&self) -> Result<()> {
+        // Don't start the server if the session is already running.
+        self.ensure_session_not_open()?;
+
+        self.deploy_server_properties()
+            .wrap_err("Failed to deploy server.properties")?;
+        self.write_player_lists()
+            .wrap_err("Failed to write player lists")?;
+
+        // Create a new session for this instance
+        self.start_session(None)?;
+
+        // Start the server
+        self.send_input_newline(&self.start_command()?)?;
+
+        Ok(())
+    }
+
+    fn update_inner(&self) -> Result<()> {
+        let installation = self
+            .installation
+            .as_ref()
+            .ok_or_else(|| eyre!("Version not specified for update"))?;
+
+        // The worlds are kept on rollbacks, so keep a copy of them before they're upgraded.
+        if self.config.game_dir().exists() {
+            self.backup()?;
+        }
+        self.install(installation)
+    }
+
+    fn backup_inner(&self) -> Result<()> {
+        // Inform users and save the map if the server is running.
+        if self.is_session_open()? {
+            // Send a backup message
+            self.send_command("say Running full backup")?;
+
+            // Save the world to disk.
+            // Via RCON, this only returns once the world has been completely written.
+            if self
+                .send_command_with_timeout("save-all flush", SAVE_TIMEOUT)?
+                .is_none()
+            {
+                // Wait for at least a minute to give minecraft enough time to write the backup
+                sleep_seconds(60);
+            }
+        }
+
+        backup_directory(
+            self.config.game_dir(),
+            self.config.create_backup_dir()?,
+            &self.config.session_name(),
+        )?;
+
+        Ok(())
+    }
+
+    fn shutdown_inner(&self) -> Result<()> {
+        // Exit if the server is not running.
+        self.ensure_session_is_open()?;
+
+        self.backup()?;
+
+        self.send_command("say Server is gracefully shutting down")?;
+        // The server might close the RCON connection before it answers the stop command.
+        if let Err(error) = self.send_command("stop") {
+            debug!("Stop command didn't finish cleanly: {error:?}");
+        }
+
+        // Give minecraft enough time to gracefully shutdown
+        self.wait_until_stopped(Duration::from_secs(60))?;
+
+        // Exit the session
+        self.send_input_newline("exit")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{create_dir_all, read_to_string, write},
+    path::PathBuf,
+};
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utils::{config::MinecraftInstance, http, prelude::*};
+
+use crate::{DEFAULT_PORT, GAME_NAME, Minecraft, RCON_PORT_OFFSET};
+
+/// The player lists of a minecraft server.
+#[derive(Clone, Copy, Debug)]
+pub enum PlayerList {
+    Ops,
+    Whitelist,
+    Bans,
+}
+
+impl PlayerList {
+    const ALL: [PlayerList; 3] = [PlayerList::Ops, PlayerList::Whitelist, PlayerList::Bans];
+
+    /// The name of the list file in the instance directory.
+    fn file_name(&self) -> &'static str {
+        match self {
+            PlayerList::Ops => "ops.json",
+            PlayerList::Whitelist => "whitelist.json",
+            PlayerList::Bans => "banned-players.json",
+        }
+    }
+
+    /// The players that're declared for this list in the instance's settings.
+    fn declared<'a>(&self, settings: &'a MinecraftInstance) -> &'a [String] {
+        match self {
+            PlayerList::Ops => &settings.ops,
+            PlayerList::Whitelist => &settings.whitelist,
+            PlayerList::Bans => &settings.bans,
+        }
+    }
+
+    /// The console command that adds a player to this list on a running server.
+    fn add_command(&self, player: &str) -> String {
+        match self {
+            PlayerList::Ops => format!("op {player}"),
+            PlayerList::Whitelist => format!("whitelist add {player}"),
+            PlayerList::Bans => format!("ban {player}"),
+        }
+    }
+
+    /// A new entry of this list, in the format the server writes it.
+    fn entry(&self, profile: &Profile) -> Value {
+        match self {
+            PlayerList::Ops => json!({
+                "uuid": profile.uuid(),
+                "name": profile.name,
+                "level": 4,
+                "bypassesPlayerLimit": false,
+            }),
+            PlayerList::Whitelist => json!({
+                "uuid": profile.uuid(),
+                "name": profile.name,
+            }),
+            PlayerList::Bans => json!({
+                "uuid": profile.uuid(),
+                "name": profile.name,
+                "created": chrono::offset::Local::now()
+                    .format("%Y-%m-%d %H:%M:%S %z")
+                    .to_string(),
+                "source": "Server",
+                "expires": "forever",
+                "reason": "Banned by an operator.",
+            }),
+        }
+    }
+}
+
+/// A player profile as it's returned by Mojang's profile API.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Profile {
+    /// The UUID without dashes.
+    id: String,
+    name: String,
+}
+
+impl Profile {
+    /// The UUID with dashes, which is the format that's used by the server's files.
+    fn uuid(&self) -> String {
+        let id = &self.id;
+        if id.len() != 32 {
+            return id.clone();
+        }
+
+        format!(
+            "{}-{}-{}-{}-{}",
+            &id[..8],
+            &id[8..12],
+            &id[12..16],
+            &id[16..20],
+            &id[20..]
+        )
+    }
+}
+
+/// Make sure a player name can be safely sent to the server console.
+fn ensure_valid_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
+    {
+        bail!("Invalid player name: {name}");
+    }
+
+    Ok(())
+}
+
+/// The `server-port` of a `server.properties` file, which defaults to the default port.
+pub fn server_port(properties: &Properties) -> Result<u16> {
+    match properties.get("server-port") {
+        Some(port) => port
+            .parse()
+            .wrap_err(format!("Found invalid server-port {port}")),
+        None => Ok(DEFAULT_PORT),
+    }
+}
+
+/// Render a value of the config for the properties file.
+fn property_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl Minecraft {
+    /// Merge the managed settings into the instance's `server.properties`.
+    ///
+    /// The properties that're declared for the instance are merged first.
+    /// Afterwards, RCON is enabled with the default password. The RCON port is derived from the
+    /// server port (just like Minecraft's defaults), so multiple instances don't collide.
+    ///
+    /// Returns whether the file has been changed.
+    pub fn deploy_server_properties(&self) -> Result<bool> {
+        let path = self.server_properties_path();
+        let mut properties = Properties::read(&path)?;
+        let original = properties.to_string();
+
+        for (key, value) in &self.instance_settings().properties {
+            properties.set(key, &property_value(value));
+        }
+
+        let rcon_port = server_port(&properties)?.saturating_add(RCON_PORT_OFFSET);
+
+        let mut secrets = HashMap::new();
+        secrets.insert("password", self.config.default_password.clone());
+        secrets.insert("rcon_port", rcon_port.to_string());
+        let template = render_secret_file(
+            &self
+                .config
+                .default_config_dir()
+                .join("minecraft/server.properties"),
+            &secrets,
+        )?;
+        properties.merge(&Properties::parse(&template));
+
+        if properties.to_string() == original {
+            return Ok(false);
+        }
+        create_dir_all(self.config.game_dir())?;
+        properties.write(&path)?;
+
+        Ok(true)
+    }
+
+    /// The `server-port` that's declared in the instance's settings.
+    pub fn declared_port(&self) -> Result<Option<u16>> {
+        let settings = self.instance_settings();
+        let Some(port) = settings.properties.get("server-port") else {
+            return Ok(None);
+        };
+        let port = property_value(port);
+        let port = port
+            .parse()
+            .wrap_err(format!("Found invalid declared server-port {port}"))?;
+
+        Ok(Some(port))
+    }
+
+    /// The cache of player profiles, which is shared by all instances.
+    fn profile_cache_path(&self) -> PathBuf {
+        self.config
+            .temp_root()
+            .join(GAME_NAME)
+            .join("profile-cache.json")
+    }
+
+    /// Look up the profiles of the given players.
+    ///
+    /// Profiles are cached, as Mojang's API is rate limited and UUIDs never change.
+    fn lookup_profiles(&self, names: &[&str]) -> Result<BTreeMap<String, Profile>> {
+        let cache_path = self.profile_cache_path();
+        let mut cache: BTreeMap<String, Profile> = if cache_path.exists() {
+            let content =
+                read_to_string(&cache_path).wrap_err(format!("Failed to read {cache_path:?}"))?;
+            serde_json::from_str(&content).wrap_err(format!("Failed to parse {cache_path:?}"))?
+        } else {
+            BTreeMap::new()
+        };
+
+        let mut changed = false;
+        for name in names {
+            ensure_valid_name(name)?;
+            let key = name.to_lowercase();
+            if cache.contains_key(&key) {
+                continue;
+            }
+
+            let url = format!("{}/{name}", self.config.minecraft.profile_url);
+            let profile: Profile = http::get_json(&url)
+                .wrap_err(format!("Couldn't look up the UUID of player {name}"))?;
+            cache.insert(key, profile);
+            changed = true;
+        }
+
+        if changed {
+            if let Some(parent) = cache_path.parent() {
+                create_dir_all(parent)?;
+            }
+            write(&cache_path, serde_json::to_string_pretty(&cache)?)
+                .wrap_err(format!("Failed to write {cache_path:?}"))?;
+        }
+
+        Ok(cache)
+    }
+
+    fn player_list_path(&self, list: PlayerList) -> PathBuf {
+        self.config.game_dir().join(list.file_name())
+    }
+
+    /// Add the declared players to the list files of a stopped server.
+    ///
+    /// Existing entries are kept as they are, so players that have been added manually stay.
+    pub fn write_player_lists(&self) -> Result<()> {
+        let settings = self.instance_settings();
+        for list in PlayerList::ALL {
+            let declared = list.declared(&settings);
+            if declared.is_empty() {
+                continue;
+            }
+
+            let path = self.player_list_path(list);
+            let mut entries: Vec<Value> = if path.exists() {
+                let content = read_to_string(&path).wrap_err(format!("Failed to read {path:?}"))?;
+                serde_json::from_str(&content).wrap_err(format!("Failed to parse {path:?}"))?
+            } else {
+                Vec::new()
+            };
+
+            let names: Vec<&str> = declared.iter().map(String::as_str).collect();
+            let profiles = self.lookup_profiles(&names)?;
+
+            let mut changed = false;
+            for name in declared {
+                let profile = &profiles[&name.to_lowercase()];
+                let exists = entries.iter().any(|entry| {
+                    entry.get("uuid").and_then(Value::as_str) == Some(profile.uuid().as_str())
+                });
+                if !exists {
+                    info!("Adding {name} to {}", list.file_name());
+                    entries.push(list.entry(profile));
+                    changed = true;
+                }
+            }
+
+            if changed {
+                write(&path, serde_json::to_string_pretty(&entries)?)
+                    .wrap_err(format!("Failed to write {path:?}"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the declared players to the lists of a running server via console commands.
+    ///
+    /// The server persists the lists by itself.
+    fn apply_player_lists(&self) -> Result<()> {
+        let settings = self.instance_settings();
+        for list in PlayerList::ALL {
+            for name in list.declared(&settings) {
+                ensure_valid_name(name)?;
+                if let Some(output) = self.send_command(&list.add_command(name))? {
+                    info!("{output}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply the declared settings of the instance.
+    ///
+    /// Player lists of a running server are updated live. Changes to the `server.properties`
+    /// only take effect after a restart.
+    pub fn sync_settings(&self) -> Result<()> {
+        if !self.is_running()? {
+            self.deploy_server_properties()?;
+            return self.write_player_lists();
+        }
+
+        self.apply_player_lists()?;
+        if self.deploy_server_properties()? {
+            warn!(
+                "{} - server.properties has changed, restart the server to apply it",
+                self.session_name()
+            );
+        }
+
+        Ok(())
+    }
+}
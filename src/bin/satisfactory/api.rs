@@ -0,0 +1,430 @@
+use std::{
+    fs::{File, read},
+    io::copy,
+    path::Path,
+    time::Duration,
+};
+
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use serde_json::{Value, json};
+use ureq::{Agent, http::Response, tls::TlsConfig};
+use utils::prelude::*;
+
+/// The boundary that separates the parts of a multipart upload.
+const BOUNDARY: &str = "----GameServerManagementBoundary";
+
+#[derive(Debug, Deserialize)]
+struct DataResponse<T> {
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorResponse {
+    error_code: String,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Token {
+    authentication_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerState {
+    server_game_state: GameState,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameState {
+    pub active_session_name: String,
+}
+
+/// A client for the HTTPS API of the Satisfactory dedicated server.
+///
+/// All functions are called via POST requests to a single endpoint.
+/// The server uses a self-signed certificate, which is why certificates aren't verified.
+/// See the `CommunityResources/DedicatedServerAPIDocs.md` of the game files.
+pub struct ApiClient {
+    agent: Agent,
+    url: String,
+    token: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(url: &str) -> Self {
+        let tls_config = TlsConfig::builder().disable_verification(true).build();
+        let config = Agent::config_builder()
+            .tls_config(tls_config)
+            .http_status_as_error(false)
+            .timeout_global(Some(Duration::from_secs(60)))
+            .build();
+
+        ApiClient {
+            agent: Agent::new_with_config(config),
+            url: url.to_string(),
+            token: None,
+        }
+    }
+
+    /// Log in without a password, which is only possible while the server hasn't been claimed.
+    pub fn passwordless_login(&mut self) -> Result<()> {
+        let token: Token = self.call(
+            "PasswordlessLogin",
+            json!({ "MinimumPrivilegeLevel": "InitialAdmin" }),
+        )?;
+        self.token = Some(token.authentication_token);
+
+        Ok(())
+    }
+
+    /// Log in as administrator.
+    pub fn login(&mut self, admin_password: &str) -> Result<()> {
+        let token: Token = self.call(
+            "PasswordLogin",
+            json!({
+                "MinimumPrivilegeLevel": "Administrator",
+                "Password": admin_password,
+            }),
+        )?;
+        self.token = Some(token.authentication_token);
+
+        Ok(())
+    }
+
+    /// Claim an unclaimed server, which sets its name and admin password.
+    pub fn claim(&mut self, server_name: &str, admin_password: &str) -> Result<()> {
+        let token: Token = self.call(
+            "ClaimServer",
+            json!({
+                "ServerName": server_name,
+                "AdminPassword": admin_password,
+            }),
+        )?;
+        // Claiming invalidates the initial token.
+        self.token = Some(token.authentication_token);
+
+        Ok(())
+    }
+
+    pub fn set_client_password(&self, password: &str) -> Result<()> {
+        self.call_empty("SetClientPassword", json!({ "Password": password }))
+    }
+
+    pub fn game_state(&self) -> Result<GameState> {
+        let state: ServerState = self.call("QueryServerState", json!({}))?;
+        Ok(state.server_game_state)
+    }
+
+    pub fn save_game(&self, name: &str) -> Result<()> {
+        self.call_empty("SaveGame", json!({ "SaveName": name }))
+    }
+
+    /// Load a save that's already on the server.
+    pub fn load_game(&self, name: &str) -> Result<()> {
+        self.call_empty(
+            "LoadGame",
+            json!({
+                "SaveName": name,
+                "EnableAdvancedGameSettings": false,
+            }),
+        )
+    }
+
+    /// Shut down the server gracefully.
+    pub fn shutdown(&self) -> Result<()> {
+        self.call_empty("Shutdown", json!({}))
+    }
+
+    /// Upload a save file and optionally load it right away.
+    pub fn upload_save(&self, path: &Path, name: &str, load: bool) -> Result<()> {
+        let file = read(path).wrap_err(format!("Failed to read save file {path:?}"))?;
+        let data = json!({
+            "function": "UploadSaveGame",
+            "data": {
+                "SaveName": name,
+                "LoadSaveGame": load,
+                "EnableAdvancedGameSettings": false,
+            },
+        });
+
+        let mut body = Vec::with_capacity(file.len() + 512);
+        body.extend_from_slice(
+            format!(
+                "--{BOUNDARY}\r\n\
+                Content-Disposition: form-data; name=\"data\"\r\n\
+                Content-Type: application/json\r\n\r\n\
+                {data}\r\n\
+                --{BOUNDARY}\r\n\
+                Content-Disposition: form-data; name=\"saveGameFile\"; filename=\"{name}.sav\"\r\n\
+                Content-Type: application/octet-stream\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&file);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        let response = self
+            .post()
+            .content_type(format!("multipart/form-data; boundary={BOUNDARY}"))
+            .send(&body[..])
+            .wrap_err("UploadSaveGame request failed")?;
+        check_response("UploadSaveGame", response)?;
+
+        Ok(())
+    }
+
+    /// Download a save file by its name.
+    pub fn download_save(&self, name: &str, dest: &Path) -> Result<()> {
+        let response = self
+            .post()
+            .send_json(json!({
+                "function": "DownloadSaveGame",
+                "data": { "SaveName": name },
+            }))
+            .wrap_err("DownloadSaveGame request failed")?;
+        let mut response = check_response("DownloadSaveGame", response)?;
+
+        let mut file = File::create(dest).wrap_err(format!("Failed to create file {dest:?}"))?;
+        copy(&mut response.body_mut().as_reader(), &mut file)
+            .wrap_err(format!("Failed to write save to {dest:?}"))?;
+
+        Ok(())
+    }
+
+    fn post(&self) -> ureq::RequestBuilder<ureq::typestate::WithBody> {
+        let request = self.agent.post(&self.url);
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    /// Call an API function and deserialize the `data` of its response.
+    fn call<T: DeserializeOwned>(&self, function: &str, data: Value) -> Result<T> {
+        let mut response = self.send(function, data)?;
+        let response: DataResponse<T> = response
+            .body_mut()
+            .read_json()
+            .wrap_err(format!("Failed to deserialize response of {function}"))?;
+
+        Ok(response.data)
+    }
+
+    /// Call an API function that doesn't respond with any data.
+    fn call_empty(&self, function: &str, data: Value) -> Result<()> {
+        self.send(function, data)?;
+        Ok(())
+    }
+
+    fn send(&self, function: &str, data: Value) -> Result<Response<ureq::Body>> {
+        debug!("Calling Satisfactory API function {function}");
+        let response = self
+            .post()
+            .send_json(json!({ "function": function, "data": data }))
+            .wrap_err(format!("{function} request failed"))?;
+
+        check_response(function, response)
+    }
+}
+
+/// Turn error responses into errors.
+fn check_response(
+    function: &str,
+    mut response: Response<ureq::Body>,
+) -> Result<Response<ureq::Body>> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    match response.body_mut().read_json::<ErrorResponse>() {
+        Ok(error) => bail!(
+            "{function} failed with {status}: {} {}",
+            error.error_code,
+            error.error_message.unwrap_or_default()
+        ),
+        Err(_) => bail!("{function} failed with {status}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    /// A received request with its headers (lowercased names) and its body.
+    struct Request {
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    }
+
+    impl Request {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        }
+
+        fn json(&self) -> Value {
+            serde_json::from_slice(&self.body).unwrap()
+        }
+    }
+
+    /// Answer one request per response with the given status and body.
+    fn serve(responses: Vec<(u16, &'static str)>) -> (String, thread::JoinHandle<Vec<Request>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/v1", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut headers = Vec::new();
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((key, value)) = line.trim_end().split_once(": ") else {
+                        break;
+                    };
+                    headers.push((key.to_lowercase(), value.to_string()));
+                }
+                let length = headers
+                    .iter()
+                    .find(|(key, _)| key == "content-length")
+                    .map(|(_, value)| value.parse().unwrap())
+                    .unwrap_or(0);
+                let mut request_body = vec![0; length];
+                reader.read_exact(&mut request_body).unwrap();
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} Status\r\nContent-Type: application/json\r\n\
+                    Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+                requests.push(Request {
+                    headers,
+                    body: request_body,
+                });
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn login_and_query_state() -> Result<()> {
+        let (url, server) = serve(vec![
+            (200, r#"{"data": {"authenticationToken": "token123"}}"#),
+            (
+                200,
+                r#"{"data": {"serverGameState": {
+                    "activeSessionName": "Factory",
+                    "numConnectedPlayers": 2,
+                    "techTier": 4
+                }}}"#,
+            ),
+        ]);
+
+        let mut client = ApiClient::new(&url);
+        client.login("hunter2")?;
+        let state = client.game_state()?;
+        assert_eq!(state.active_session_name, "Factory");
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0].json(),
+            json!({
+                "function": "PasswordLogin",
+                "data": {"MinimumPrivilegeLevel": "Administrator", "Password": "hunter2"},
+            })
+        );
+        assert_eq!(requests[0].header("authorization"), None);
+        assert_eq!(requests[1].json()["function"], "QueryServerState");
+        assert_eq!(requests[1].header("authorization"), Some("Bearer token123"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty_responses() -> Result<()> {
+        // Functions without data answer with `204 No Content`.
+        let (url, server) = serve(vec![(204, "")]);
+
+        ApiClient::new(&url).save_game("Factory_autosave")?;
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0].json(),
+            json!({"function": "SaveGame", "data": {"SaveName": "Factory_autosave"}})
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn error_responses() {
+        let (url, server) = serve(vec![
+            (
+                401,
+                r#"{"errorCode": "wrong_password", "errorMessage": "Invalid password"}"#,
+            ),
+            (500, "not json"),
+        ]);
+
+        let mut client = ApiClient::new(&url);
+        let error = client.login("wrong").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "PasswordLogin failed with 401 Unauthorized: wrong_password Invalid password"
+        );
+        let error = client.shutdown().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Shutdown failed with 500 Internal Server Error"
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn upload_save() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("satisfactory-upload-{}", std::process::id()));
+        std::fs::write(&path, b"save content")?;
+        let (url, server) = serve(vec![(204, "")]);
+
+        let result = ApiClient::new(&url).upload_save(&path, "Imported", true);
+        std::fs::remove_file(&path)?;
+        result?;
+
+        let requests = server.join().unwrap();
+        let content_type = requests[0].header("content-type").unwrap();
+        assert_eq!(
+            content_type,
+            format!("multipart/form-data; boundary={BOUNDARY}")
+        );
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains(r#""SaveName":"Imported""#));
+        assert!(body.contains(r#""LoadSaveGame":true"#));
+        assert!(body.contains("filename=\"Imported.sav\"\r\nContent-Type: application/octet-stream\r\n\r\nsave content\r\n"));
+        assert!(body.ends_with(&format!("--{BOUNDARY}--\r\n")));
+
+        Ok(())
+    }
+}
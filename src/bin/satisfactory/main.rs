@@ -0,0 +1,288 @@
+use std::{
+    fs::create_dir,
+    os::unix::fs::symlink,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use color_eyre::{Result, eyre::WrapErr};
+use utils::prelude::*;
+
+mod api;
+mod saves;
+
+use api::ApiClient;
+
+#[derive(Debug, Parser)]
+enum SubCommand {
+    Startup,
+    Shutdown,
+    Backup,
+    /// Restore the game files from the snapshot taken before the last update.
+    Rollback,
+    /// Check whether a new server build is available.
+    CheckUpdate,
+    Update {
+        /// Only update if a new server build is available.
+        #[clap(long)]
+        if_needed: bool,
+    },
+    /// Claim a freshly installed server.
+    ///
+    /// This sets the server name and admin password from the config.
+    /// If a client password is configured, it's set as well.
+    Claim,
+    /// Force the server to save the game.
+    Save {
+        /// The name of the save. Defaults to the name of the active session.
+        name: Option<String>,
+    },
+    /// Upload a save file to the running server.
+    UploadSave {
+        file: PathBuf,
+        /// The name of the save on the server. Defaults to the file name.
+        #[clap(long)]
+        name: Option<String>,
+        /// Load the save right after uploading it.
+        #[clap(long)]
+        load: bool,
+    },
+    /// Replace the server's current save with a local save file.
+    ///
+    /// The server is stopped, its saves are backed up and it's started again with the given save.
+    ImportSave {
+        file: PathBuf,
+    },
+    /// Download a save file from the running server.
+    DownloadSave {
+        name: String,
+        /// Where to write the save to. Defaults to `{name}.sav` in the current directory.
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "Satisfactory",
+    about = "A small binary to manage my Satisfactory server"
+)]
+struct CliArguments {
+    #[clap(subcommand)]
+    pub cmd: SubCommand,
+}
+
+const GAME_NAME: &str = "satisfactory";
+const PORT: u16 = 7777;
+
+fn main() -> Result<()> {
+    install_tracing()?;
+
+    // Parse commandline options.
+    let args = CliArguments::parse();
+    let server = Satisfactory::new()?;
+
+    match args.cmd {
+        SubCommand::Startup => server.startup(),
+        SubCommand::Shutdown => server.shutdown(),
+        SubCommand::Backup => server.backup(),
+        SubCommand::Rollback => server.rollback(),
+        SubCommand::CheckUpdate => server.check_update(),
+        SubCommand::Update { if_needed } => {
+            if if_needed {
+                server.update_if_needed()
+            } else {
+                server.update()
+            }
+        }
+        SubCommand::Claim => server.claim(),
+        SubCommand::Save { name } => server.save(name),
+        SubCommand::UploadSave { file, name, load } => {
+            let name = match name {
+                Some(name) => name,
+                None => file
+                    .file_stem()
+                    .ok_or_else(|| eyre!("Couldn't get save name from {file:?}"))?
+                    .to_string_lossy()
+                    .to_string(),
+            };
+            server.api()?.upload_save(&file, &name, load)?;
+            info!("Uploaded {file:?} as {name}");
+            Ok(())
+        }
+        SubCommand::ImportSave { file } => server.import_save(&file),
+        SubCommand::DownloadSave { name, output } => {
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{name}.sav")));
+            server.api()?.download_save(&name, &output)?;
+            info!("Downloaded {name} to {output:?}");
+            Ok(())
+        }
+    }
+}
+
+struct Satisfactory {
+    config: Config,
+}
+
+impl Satisfactory {
+    fn new() -> Result<Self> {
+        let config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        Ok(Self { config })
+    }
+
+    /// The directory that contains the server's save games and its settings.
+    fn save_dir(&self) -> PathBuf {
+        expand_home("~/.config/Epic/FactoryGame/Saved/SaveGames/server")
+    }
+
+    /// Get an API client that's logged in as administrator.
+    fn api(&self) -> Result<ApiClient> {
+        let settings = &self.config.satisfactory;
+        let mut api = ApiClient::new(&settings.api_url);
+        api.login(&settings.admin_password(&self.config.default_password))
+            .wrap_err("Failed to log in to the server API")?;
+
+        Ok(api)
+    }
+
+    fn claim(&self) -> Result<()> {
+        let settings = &self.config.satisfactory;
+        let mut api = ApiClient::new(&settings.api_url);
+        api.passwordless_login()
+            .wrap_err("Failed to log in, the server might already be claimed")?;
+        api.claim(
+            &settings.server_name,
+            &settings.admin_password(&self.config.default_password),
+        )?;
+
+        if let Some(password) = &settings.client_password {
+            api.set_client_password(password)?;
+        }
+        info!("Claimed server as {}", settings.server_name);
+
+        Ok(())
+    }
+
+    /// Save the game under the given name or the name of the active session.
+    fn save(&self, name: Option<String>) -> Result<()> {
+        let api = self.api()?;
+        let name = match name {
+            Some(name) => name,
+            None => api.game_state()?.active_session_name,
+        };
+        api.save_game(&name)?;
+        info!("Saved game as {name}");
+
+        Ok(())
+    }
+
+    /// Wait for the server to release its port.
+    fn wait_until_stopped(&self, timeout: Duration) -> Result<()> {
+        let start = Instant::now();
+        while is_port_bound(PORT, Protocol::Udp)? {
+            if start.elapsed() > timeout {
+                warn!(
+                    "{} - Server didn't stop within {} seconds",
+                    self.session_name(),
+                    timeout.as_secs()
+                );
+                break;
+            }
+            sleep_seconds(1);
+        }
+
+        Ok(())
+    }
+}
+
+impl TmuxServer for Satisfactory {}
+
+impl SteamServer for Satisfactory {
+    /// The Satisfactory server has the id 1690800.
+    fn app_id(&self) -> u32 {
+        1690800
+    }
+}
+
+impl GameServer for Satisfactory {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    fn is_ready(&self) -> Result<bool> {
+        self.is_port_bound_by_session(PORT, Protocol::Udp)
+    }
+
+    fn startup_inner(&self) -> Result<()> {
+        // Don't start the server if the session is already running.
+        self.ensure_session_not_open()?;
+
+        // Satisfactory expects the steamclient.so library to be at a different location.
+        // We create a symlink to the expected location.
+        let folder = expand_home("~/.steam/steamcmd/sdk64/");
+        if !folder.exists() {
+            create_dir(folder)?;
+        }
+        let link_src = expand_home("~/.steam/steamcmd/linux64/steamclient.so");
+        let link_dest = expand_home("~/.steam/steamcmd/sdk64/steamclient.so");
+        if link_src.exists() && !link_dest.exists() {
+            symlink(link_src, link_dest)?;
+        }
+
+        // Create a new session for this instance
+        self.start_session(None)?;
+
+        self.send_input_newline(concat!(
+            "./FactoryServer.sh ",
+            "--ReliablePort=7778 ",
+            "--ExternalReliablePort=7778",
+        ))?;
+
+        Ok(())
+    }
+
+    fn backup_inner(&self) -> Result<()> {
+        // Make sure the backup contains the current state of the game.
+        if self.is_session_open()?
+            && let Err(error) = self.save(None)
+        {
+            warn!("Failed to save the game before the backup: {error:?}");
+        }
+
+        backup_directory(self.save_dir(), self.config.create_backup_dir()?, GAME_NAME)?;
+
+        Ok(())
+    }
+
+    fn update_inner(&self) -> Result<()> {
+        // Run a quick backup for good measure.
+        self.backup()?;
+
+        self.steamcmd_update()
+    }
+
+    fn shutdown_inner(&self) -> Result<()> {
+        // Exit if the server is not running.
+        self.ensure_session_is_open()?;
+
+        // The backup saves the game, so there's no need to save it again before the shutdown.
+        self.backup()?;
+
+        // Prefer a graceful shutdown via the API.
+        match self.api().and_then(|api| api.shutdown()) {
+            Ok(()) => self.wait_until_stopped(Duration::from_secs(60))?,
+            Err(error) => {
+                warn!("API shutdown failed, falling back to Ctrl+C: {error:?}");
+                self.send_ctrl_c()?;
+            }
+        }
+        self.send_input_newline("exit")?;
+
+        Ok(())
+    }
+}
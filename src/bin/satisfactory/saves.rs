@@ -0,0 +1,95 @@
+use std::{
+    fs::{copy, create_dir_all, read_dir, rename},
+    path::{Path, PathBuf},
+};
+
+use utils::prelude::*;
+
+use crate::Satisfactory;
+
+impl Satisfactory {
+    /// Find the most recently written save game in the server's save directory.
+    ///
+    /// The server's settings are stored in `ServerSettings.{port}.sav` files, which are ignored.
+    fn newest_save(&self) -> Result<Option<PathBuf>> {
+        let save_dir = self.save_dir();
+        if !save_dir.exists() {
+            return Ok(None);
+        }
+
+        let mut newest = None;
+        for entry in read_dir(&save_dir).wrap_err(format!("Failed to read {save_dir:?}"))? {
+            let path = entry?.path();
+            let is_save = path.extension().is_some_and(|extension| extension == "sav");
+            let is_settings = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("ServerSettings"));
+            if !is_save || is_settings {
+                continue;
+            }
+
+            let modified = path.metadata()?.modified()?;
+            if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+                newest = Some((modified, path));
+            }
+        }
+
+        Ok(newest.map(|(_, path)| path))
+    }
+
+    /// Replace the server's current save with a local save file and load it.
+    ///
+    /// The server always loads its newest save on startup. Hence, the save of the fresh world
+    /// that's created on the first startup is moved aside to `{name}.sav.replaced`, so the
+    /// server can't pick it up anymore. All saves are backed up beforehand.
+    pub fn import_save(&self, file: &Path) -> Result<()> {
+        if file.extension().is_none_or(|extension| extension != "sav") {
+            bail!("{file:?} isn't a Satisfactory save file");
+        }
+        if !file.is_file() {
+            bail!("Couldn't find save file {file:?}");
+        }
+        let file_name = file
+            .file_name()
+            .ok_or_else(|| eyre!("Couldn't get file name of {file:?}"))?;
+        let save_dir = self.save_dir();
+        let dest = save_dir.join(file_name);
+        if dest.exists() {
+            bail!("The server already has a save {dest:?}, rename the file to import it");
+        }
+
+        // Shutting down also backs up the saves.
+        if self.is_running()? {
+            self.shutdown()?;
+        } else {
+            self.backup()?;
+        }
+
+        if let Some(placeholder) = self.newest_save()? {
+            let mut replaced = placeholder.clone().into_os_string();
+            replaced.push(".replaced");
+            info!("Moving placeholder save {placeholder:?} to {replaced:?}");
+            rename(&placeholder, &replaced)
+                .wrap_err(format!("Failed to move {placeholder:?} to {replaced:?}"))?;
+        }
+
+        create_dir_all(&save_dir)?;
+        info!("Copying {file:?} to {dest:?}");
+        copy(file, &dest).wrap_err(format!("Failed to copy {file:?} to {dest:?}"))?;
+
+        self.startup()?;
+        self.wait_until_ready()?;
+
+        // The server should pick up the save by itself, but explicitly loading it makes sure the
+        // right session is running.
+        let name = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.api()
+            .and_then(|api| api.load_game(&name))
+            .wrap_err(format!(
+                "Failed to load {name} via the API, the server might be running another session"
+            ))
+    }
+}
@@ -14,7 +14,13 @@ enum SubCommand {
         #[clap(value_enum, default_value_t = GameMode::Am)]
         gamemode: GameMode,
     },
-    Shutdown,
+    Shutdown {
+        /// Only shut the server down if no players are online.
+        #[clap(long)]
+        if_idle: bool,
+    },
+    /// Show whether the server is running, its map and its players.
+    Status,
 }
 
 #[derive(Debug, Parser)]
@@ -22,23 +28,38 @@ enum SubCommand {
 struct CliArguments {
     #[clap(subcommand)]
     pub cmd: SubCommand,
+    /// The instance to manage. Instances are declared in the `[instances.ut]` section of
+    /// the config. The default instance is used if none is given.
+    #[clap(long, global = true)]
+    pub instance: Option<String>,
 }
 
 const GAME_NAME: &str = "ut";
+const PORT: u16 = 7777;
+/// The GameSpy query port, which is the game port + 10.
+const QUERY_PORT: u16 = 7787;
+const DEFAULT_MAP: &str = "DM-Asbestos";
 
 fn main() -> Result<()> {
     install_tracing()?;
 
     // Parse commandline options.
     let args = CliArguments::parse();
-    let mut server = Ut2004::new()?;
+    let mut server = Ut2004::new(args.instance)?;
 
     match args.cmd {
         SubCommand::Startup { gamemode } => {
             server.gamemode = gamemode;
             server.startup()
         }
-        SubCommand::Shutdown => server.shutdown(),
+        SubCommand::Shutdown { if_idle } => {
+            if if_idle {
+                server.shutdown_if_idle()
+            } else {
+                server.shutdown()
+            }
+        }
+        SubCommand::Status => server.status(),
     }
 }
 
@@ -48,8 +69,9 @@ struct Ut2004 {
 }
 
 impl Ut2004 {
-    fn new() -> Result<Self> {
-        let config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+    fn new(instance: Option<String>) -> Result<Self> {
+        let mut config = Config::new(GAME_NAME).wrap_err("Failed to read config")?;
+        config.select_instance(instance)?;
         Ok(Self {
             config,
             gamemode: GameMode::default(),
@@ -63,6 +85,23 @@ impl GameServer for Ut2004 {
     fn config(&self) -> &Config {
         &self.config
     }
+
+    fn is_running(&self) -> Result<bool> {
+        self.is_session_open()
+    }
+
+    fn query_client(&self) -> Result<Option<Box<dyn Query>>> {
+        Ok(Some(Box::new(GameSpyClient::new(format!(
+            "127.0.0.1:{}",
+            self.config.port(QUERY_PORT)?
+        )))))
+    }
+
+    /// The server settings and logs aren't part of the installation.
+    fn preserved_paths(&self) -> Vec<&'static str> {
+        vec!["System/ut2004.ini", "System/User.ini", "UserLogs"]
+    }
+
     fn startup_inner(&self) -> Result<()> {
         // Don't start the server if the session is already running.
         self.ensure_session_not_open()?;
@@ -73,8 +112,9 @@ impl GameServer for Ut2004 {
         let server_command = match self.gamemode {
             GameMode::Tam => std::concat!(
                 "./ucc-bin server ",
-                "\"DM-Asbestos",
+                "\"{{ map }}",
                 "?game=3SPNv3141.TeamArenaMaster",
+                "{{ game_password }}",
                 "?AdminName=private",
                 "?AdminPassword={{ password }}\" ",
                 "ini=ut2004.ini ",
@@ -82,8 +122,9 @@ impl GameServer for Ut2004 {
             ),
             GameMode::Am => std::concat!(
                 "./ucc-bin server ",
-                "\"DM-Asbestos",
+                "\"{{ map }}",
                 "?game=3SPNv3141.ArenaMaster",
+                "{{ game_password }}",
                 "?AdminName=private",
                 "?AdminPassword={{ password }}\" ",
                 "ini=ut2004.ini ",
@@ -91,8 +132,18 @@ impl GameServer for Ut2004 {
             ),
         };
 
-        let server_command =
-            server_command.replace("{{ password }}", &self.config.default_password);
+        // The default instance is public, instances only require a password if they declare one.
+        let game_password = self
+            .config
+            .instance_settings()
+            .password
+            .map(|password| format!("?GamePassword={password}"))
+            .unwrap_or_default();
+        let server_command = server_command
+            .replace("{{ map }}", &self.config.world(DEFAULT_MAP))
+            .replace("{{ game_password }}", &game_password)
+            .replace("{{ password }}", &self.config.default_password);
+        let server_command = format!("{server_command} -port={}", self.config.port(PORT)?);
         self.send_input_newline(&server_command)?;
 
         Ok(())
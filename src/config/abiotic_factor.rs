@@ -0,0 +1,52 @@
+use serde_derive::{Deserialize, Serialize};
+
+fn default_world() -> String {
+    "MadLab".into()
+}
+
+fn default_server_name() -> String {
+    "MadLab Hamburg".into()
+}
+
+fn default_port() -> u16 {
+    7780
+}
+
+fn default_query_port() -> u16 {
+    7781
+}
+
+fn default_max_players() -> u8 {
+    6
+}
+
+/// All settings which are used by both, the client and the daemon
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AbioticFactor {
+    /// The world that's loaded on startup. An instance's `world` takes precedence.
+    #[serde(default = "default_world")]
+    pub world: String,
+    /// The name of the server in the server browser.
+    #[serde(default = "default_server_name")]
+    pub server_name: String,
+    /// The game port. An instance's `port_offset` is added to it.
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// The Steam query port. An instance's `port_offset` is added to it.
+    #[serde(default = "default_query_port")]
+    pub query_port: u16,
+    #[serde(default = "default_max_players")]
+    pub max_players: u8,
+}
+
+impl Default for AbioticFactor {
+    fn default() -> Self {
+        Self {
+            world: default_world(),
+            server_name: default_server_name(),
+            port: default_port(),
+            query_port: default_query_port(),
+            max_players: default_max_players(),
+        }
+    }
+}
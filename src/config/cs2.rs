@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use super::Rcon;
+
+/// The game type and game mode presets of Counter-Strike 2.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Display,
+    EnumString,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Cs2Mode {
+    Casual,
+    #[default]
+    Competitive,
+    Wingman,
+    Deathmatch,
+}
+
+impl Cs2Mode {
+    /// The `game_type` and `game_mode` convars of the preset.
+    pub fn game_type_and_mode(&self) -> (u8, u8) {
+        match self {
+            Cs2Mode::Casual => (0, 0),
+            Cs2Mode::Competitive => (0, 1),
+            Cs2Mode::Wingman => (0, 2),
+            Cs2Mode::Deathmatch => (1, 2),
+        }
+    }
+}
+
+/// All settings which are used by both, the client and the daemon
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Cs2 {
+    /// This token can be received over here:
+    /// https://steamcommunity.com/dev/managegameservers
+    ///
+    /// The app Id for the CS2 client is 730. This should be used!
+    pub login_token: String,
+    /// The preset that's used if none is passed on startup.
+    #[serde(default)]
+    pub mode: Cs2Mode,
+    /// Send console commands via RCON instead of the tmux session.
+    /// The RCON port of source servers is the game port, usually 27015.
+    #[serde(default)]
+    pub rcon: Option<Rcon>,
+    /// The map pool of each mode, which is written to the `mapcycle.txt` and `maplist.txt`.
+    /// The map cycle isn't touched if no maps are declared for a mode.
+    #[serde(default)]
+    pub maps: BTreeMap<Cs2Mode, Vec<String>>,
+}
+
+impl Cs2 {
+    /// The map pool of a mode.
+    pub fn maps(&self, mode: Cs2Mode) -> &[String] {
+        self.maps.get(&mode).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_per_mode() {
+        let config: Cs2 = toml::from_str(
+            r#"
+login_token = "token"
+
+[maps]
+casual = ["de_dust2", "workshop/3070284539"]
+wingman = ["de_shortdust"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.maps(Cs2Mode::Casual),
+            ["de_dust2", "workshop/3070284539"]
+        );
+        assert_eq!(config.maps(Cs2Mode::Wingman), ["de_shortdust"]);
+        assert!(config.maps(Cs2Mode::Competitive).is_empty());
+    }
+}
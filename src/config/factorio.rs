@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::Rcon;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Factorio {
+    /// The base url of the factorio.com website.
+    /// This only needs to be changed to test against a local server.
+    #[serde(default = "default_url")]
+    pub url: String,
+    /// The base url of the factorio mod portal.
+    /// This only needs to be changed to test against a local server.
+    #[serde(default = "default_mod_portal_url")]
+    pub mod_portal_url: String,
+    /// The factorio.com username, which is needed to download mods.
+    #[serde(default)]
+    pub username: String,
+    /// The factorio.com token, which is needed to download mods.
+    /// It can be found over here: https://factorio.com/profile
+    #[serde(default)]
+    pub token: String,
+    /// The mods that should be installed, mapped to their version.
+    /// Use `latest` to always install the newest release of a mod for the installed game version.
+    ///
+    /// If no mods are declared, the mods directory isn't touched at all.
+    #[serde(default)]
+    pub mods: BTreeMap<String, String>,
+    /// Enable RCON and send console commands via RCON instead of the tmux session.
+    #[serde(default)]
+    pub rcon: Option<Rcon>,
+}
+
+impl Default for Factorio {
+    fn default() -> Self {
+        Self {
+            url: default_url(),
+            mod_portal_url: default_mod_portal_url(),
+            username: String::new(),
+            token: String::new(),
+            mods: BTreeMap::new(),
+            rcon: None,
+        }
+    }
+}
+
+fn default_url() -> String {
+    "https://factorio.com".into()
+}
+
+fn default_mod_portal_url() -> String {
+    "https://mods.factorio.com".into()
+}
@@ -1,11 +1,130 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
 use serde_derive::{Deserialize, Serialize};
 
+use super::Rcon;
+use crate::errors::*;
+
 /// All settings which are used by both, the client and the daemon
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Garrys {
     /// This token can be received over here:
     /// https://steamcommunity.com/dev/managegameservers
     ///
     /// The app Id for the CS:GO client is 730. This should be used!
     pub steam_web_api_key: String,
+    /// The name of the server in the server browser.
+    #[serde(default = "default_hostname")]
+    pub hostname: String,
+    /// Send console commands via RCON instead of the tmux session.
+    /// The RCON port of source servers is the game port, usually 27015.
+    #[serde(default)]
+    pub rcon: Option<Rcon>,
+    /// Download the workshop collection of the game mode before startup and mount it as local
+    /// addons, instead of letting the server download it on every boot.
+    #[serde(default)]
+    pub workshop_sync: bool,
+    /// Game modes by name, e.g. `[garrys.modes.murder]`.
+    /// They're added to the default modes (ttt, prophunt and zombie) and can override them.
+    #[serde(default)]
+    pub modes: BTreeMap<String, GarrysMode>,
+}
+
+impl Garrys {
+    /// All available game modes, i.e. the default modes and the ones from the config.
+    pub fn modes(&self) -> BTreeMap<String, GarrysMode> {
+        let mut modes = default_modes();
+        modes.extend(self.modes.clone());
+        modes
+    }
+
+    /// Get a game mode by its name.
+    pub fn mode(&self, name: &str) -> Result<GarrysMode> {
+        let mut modes = self.modes();
+        match modes.remove(name) {
+            Some(mode) => Ok(mode),
+            None => bail!(
+                "Unknown game mode {name}. Available modes: {}",
+                modes.into_keys().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+impl Default for Garrys {
+    fn default() -> Self {
+        Self {
+            steam_web_api_key: String::new(),
+            hostname: default_hostname(),
+            rcon: None,
+            workshop_sync: false,
+            modes: BTreeMap::new(),
+        }
+    }
+}
+
+/// A game mode, which is selected on startup.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GarrysMode {
+    /// The server config template, relative to the default config dir, e.g. `garrys/ttt.cfg`.
+    /// It's deployed as the `server.cfg`. The existing `server.cfg` is kept if none is set.
+    #[serde(default)]
+    pub config_file: Option<PathBuf>,
+    /// The gamemode that's passed to the server, e.g. `terrortown`.
+    pub gamemode: String,
+    /// The map that's loaded on startup. An instance's `world` takes precedence.
+    pub map: String,
+    /// The id of the workshop collection that's downloaded by the server.
+    #[serde(default)]
+    pub workshop_collection: Option<u64>,
+    #[serde(default)]
+    pub max_players: Option<u32>,
+    /// Additional convars that're passed on the command line, e.g. `sv_lan = "0"`.
+    #[serde(default)]
+    pub convars: BTreeMap<String, String>,
+    /// The map pool, which is written to the `mapcycle.txt` and `maplist.txt`.
+    /// The map cycle isn't touched if no maps are declared.
+    #[serde(default)]
+    pub maps: Vec<String>,
+}
+
+fn default_hostname() -> String {
+    "Nukesors_garry_playground".into()
+}
+
+fn default_modes() -> BTreeMap<String, GarrysMode> {
+    let mode = |config_file: Option<&str>, gamemode: &str, map: &str, collection: u64| GarrysMode {
+        config_file: config_file.map(PathBuf::from),
+        gamemode: gamemode.into(),
+        map: map.into(),
+        workshop_collection: Some(collection),
+        max_players: None,
+        convars: BTreeMap::new(),
+        maps: Vec::new(),
+    };
+
+    BTreeMap::from([
+        (
+            "ttt".to_string(),
+            mode(
+                Some("garrys/ttt.cfg"),
+                "terrortown",
+                "ttt_rooftops_2016_v1",
+                2089206449,
+            ),
+        ),
+        (
+            "prophunt".to_string(),
+            mode(
+                Some("garrys/prop_hunt.cfg"),
+                "prop_hunt",
+                "ph_indoorpool",
+                2090357275,
+            ),
+        ),
+        (
+            "zombie".to_string(),
+            mode(None, "zombiesurvival", "zs_cleanoffice_v2", 157384458),
+        ),
+    ])
 }
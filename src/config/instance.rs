@@ -0,0 +1,17 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// Overrides for a single instance of a game, e.g. `[instances.factorio.second]`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Instance {
+    /// The offset that's added to all ports of the game.
+    /// It's required, so the ports of an instance never depend on the other instances.
+    #[serde(default)]
+    pub port_offset: Option<u16>,
+    /// The password players need to join. The `default_password` is used if none is set.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// The world, save or map that's loaded by the instance.
+    /// Each game uses its own default if none is set.
+    #[serde(default)]
+    pub world: Option<String>,
+}
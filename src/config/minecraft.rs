@@ -0,0 +1,140 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde_derive::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Minecraft {
+    /// The url of Mojang's version manifest, which lists all released server versions.
+    /// This only needs to be changed to test against a local server.
+    #[serde(default = "default_manifest_url")]
+    pub manifest_url: String,
+    /// The base url of PaperMC's download API.
+    #[serde(default = "default_paper_url")]
+    pub paper_url: String,
+    /// The base url of Fabric's meta API.
+    #[serde(default = "default_fabric_url")]
+    pub fabric_url: String,
+    /// The base url of the Forge maven repository.
+    #[serde(default = "default_forge_url")]
+    pub forge_url: String,
+    /// The base url of the Forge files website, which lists the recommended Forge versions.
+    #[serde(default = "default_forge_files_url")]
+    pub forge_files_url: String,
+    /// The base url of the NeoForge maven repository.
+    #[serde(default = "default_neoforge_url")]
+    pub neoforge_url: String,
+    /// The base url of Mojang's profile API, which is used to look up player UUIDs.
+    #[serde(default = "default_profile_url")]
+    pub profile_url: String,
+    /// Instance specific settings, by instance name.
+    #[serde(default)]
+    pub instances: BTreeMap<String, MinecraftInstance>,
+}
+
+impl Minecraft {
+    /// Get the settings of an instance. Unknown instances use the default settings.
+    pub fn instance(&self, name: &str) -> MinecraftInstance {
+        self.instances.get(name).cloned().unwrap_or_default()
+    }
+}
+
+impl Default for Minecraft {
+    fn default() -> Self {
+        Self {
+            manifest_url: default_manifest_url(),
+            paper_url: default_paper_url(),
+            fabric_url: default_fabric_url(),
+            forge_url: default_forge_url(),
+            forge_files_url: default_forge_files_url(),
+            neoforge_url: default_neoforge_url(),
+            profile_url: default_profile_url(),
+            instances: BTreeMap::new(),
+        }
+    }
+}
+
+/// The server software that's used to run an instance.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Display, EnumString, PartialEq, Eq, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Loader {
+    #[default]
+    Vanilla,
+    Paper,
+    Fabric,
+    Forge,
+    NeoForge,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MinecraftInstance {
+    /// The server software of the instance.
+    /// If none is set, the installed loader is kept, which defaults to vanilla.
+    #[serde(default)]
+    pub loader: Option<Loader>,
+    /// The version of the loader, e.g. the Paper build or the Forge version.
+    /// The newest version for the instance's minecraft version is used if none is set.
+    #[serde(default)]
+    pub loader_version: Option<String>,
+    /// The initial heap size, e.g. `2G`. Passed as `-Xms`.
+    #[serde(default)]
+    pub min_memory: Option<String>,
+    /// The maximum heap size, e.g. `8G`. Passed as `-Xmx`.
+    #[serde(default)]
+    pub max_memory: Option<String>,
+    /// Use Aikar's G1GC flags, which are tuned for minecraft servers.
+    /// See https://docs.papermc.io/paper/aikars-flags
+    #[serde(default)]
+    pub aikar_flags: bool,
+    /// Additional JVM arguments.
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    /// The home directory of the JDK that runs the server, e.g. `/usr/lib/jvm/java-21-openjdk`.
+    /// The `java` binary from the `PATH` is used if none is set.
+    #[serde(default)]
+    pub java_home: Option<PathBuf>,
+    /// Values that're merged into the instance's `server.properties`, e.g. `max-players = 20`.
+    /// Keys that aren't declared here are left untouched.
+    #[serde(default)]
+    pub properties: BTreeMap<String, toml::Value>,
+    /// Players that're made operators.
+    #[serde(default)]
+    pub ops: Vec<String>,
+    /// Players that're added to the whitelist.
+    #[serde(default)]
+    pub whitelist: Vec<String>,
+    /// Players that're banned.
+    #[serde(default)]
+    pub bans: Vec<String>,
+}
+
+fn default_manifest_url() -> String {
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json".into()
+}
+
+fn default_paper_url() -> String {
+    "https://fill.papermc.io".into()
+}
+
+fn default_fabric_url() -> String {
+    "https://meta.fabricmc.net".into()
+}
+
+fn default_forge_url() -> String {
+    "https://maven.minecraftforge.net".into()
+}
+
+fn default_forge_files_url() -> String {
+    "https://files.minecraftforge.net".into()
+}
+
+fn default_neoforge_url() -> String {
+    "https://maven.neoforged.net".into()
+}
+
+fn default_profile_url() -> String {
+    "https://api.mojang.com/users/profiles/minecraft".into()
+}
@@ -1,7 +1,9 @@
 use std::{
+    collections::BTreeMap,
     fs::{File, create_dir_all},
     io::prelude::*,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use color_eyre::eyre::ContextCompat;
@@ -10,14 +12,36 @@ use shellexpand::tilde;
 
 use crate::errors::*;
 
-mod cs_go;
+mod abiotic_factor;
+mod cs2;
+mod factorio;
 mod garrys;
+mod instance;
+mod minecraft;
+mod rcon;
+mod satisfactory;
 mod terraria;
 
-use cs_go::CsGo;
+use abiotic_factor::AbioticFactor;
+use cs2::Cs2;
+pub use cs2::Cs2Mode;
+use factorio::Factorio;
 use garrys::Garrys;
+pub use garrys::GarrysMode;
+pub use instance::Instance;
+use minecraft::Minecraft;
+pub use minecraft::{Loader, MinecraftInstance};
+pub use rcon::Rcon;
+use satisfactory::Satisfactory;
 use terraria::Terraria;
 
+/// How long a server may take to become ready after startup, unless configured otherwise.
+const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(300);
+
+fn default_steam_api_url() -> String {
+    "https://api.steampowered.com".into()
+}
+
 pub fn expand(path: &Path) -> PathBuf {
     PathBuf::from(tilde(&path.to_string_lossy()).into_owned())
 }
@@ -27,9 +51,14 @@ pub struct Config {
     /// The name of the game this config is currently used with.
     #[serde(default)]
     pub game_name: String,
-    /// The name of the game this config is currently used with.
+    /// The instance of the game this config is currently used with.
     #[serde(default)]
     pub instance: Option<String>,
+    /// Whether instances live inside the game's directory, i.e. `{game_name}/{instance}`.
+    /// Only Minecraft does this, as it doesn't have a default instance.
+    /// All other games keep their instances next to the default instance.
+    #[serde(skip)]
+    pub nested_instances: bool,
     /// The root directory for all game files.
     game_file_root: PathBuf,
     /// The root location where games can write their backups to.
@@ -43,13 +72,34 @@ pub struct Config {
     /// For steam games, use this ID as the admin.
     #[serde(default)]
     pub admin_steam_id: String,
+    /// The base url of the Steam Web API, which is used to resolve workshop collections.
+    /// This only needs to be changed to test against a local server.
+    #[serde(default = "default_steam_api_url")]
+    pub steam_api_url: String,
+    /// How long servers may take to become ready after startup, in seconds.
+    /// Keyed by game (e.g. `minecraft`) or by session for single instances (e.g.
+    /// `minecraft-modpack`).
+    #[serde(default)]
+    pub ready_timeouts: BTreeMap<String, u64>,
     /// Game specific sub-configurations
     #[serde(default)]
-    pub cs_go: CsGo,
+    pub abiotic_factor: AbioticFactor,
+    /// The `cs_go` section of the retired CS:GO server is still accepted.
+    #[serde(default, alias = "cs_go")]
+    pub cs2: Cs2,
+    #[serde(default)]
+    pub factorio: Factorio,
     #[serde(default)]
     pub garrys: Garrys,
     #[serde(default)]
+    pub minecraft: Minecraft,
+    #[serde(default)]
+    pub satisfactory: Satisfactory,
+    #[serde(default)]
     pub terraria: Terraria,
+    /// Instance specific overrides, by game name and instance name.
+    #[serde(default)]
+    pub instances: BTreeMap<String, BTreeMap<String, Instance>>,
 }
 
 impl Config {
@@ -77,15 +127,23 @@ impl Config {
         let default_config = Config {
             game_name: game_name.to_string(),
             instance: None,
+            nested_instances: false,
             game_file_root: "~/game_servers/games/".into(),
             backup_root: "/var/lib/backup/games/".into(),
             temp_file_root: "~/game_servers/tmp/".into(),
             default_password: "your pass".into(),
             admin_steam_id: "".into(),
+            steam_api_url: default_steam_api_url(),
+            ready_timeouts: BTreeMap::new(),
             default_config_dir: "~/server_management".into(),
-            cs_go: CsGo::default(),
+            abiotic_factor: AbioticFactor::default(),
+            cs2: Cs2::default(),
+            factorio: Factorio::default(),
             garrys: Garrys::default(),
+            minecraft: Minecraft::default(),
+            satisfactory: Satisfactory::default(),
             terraria: Terraria::default(),
+            instances: BTreeMap::new(),
         };
         default_config.write()?;
 
@@ -135,13 +193,104 @@ impl Config {
     }
 
     /// Return the sub-path for this game.
-    /// Either `game_name` or `{game_name}/{instance}` if an instance is selected.
+    /// Either `game_name` or `{game_name}-{instance}` if an instance is selected.
+    ///
+    /// Instances must not live inside the directories of the default instance, as those are
+    /// replaced during updates and rollbacks.
+    /// Games with [Config::nested_instances] use `{game_name}/{instance}` instead.
     pub fn game_subpath(&self) -> PathBuf {
-        let mut path = PathBuf::from(&self.game_name);
-        if let Some(instance) = &self.instance {
-            path = path.join(instance)
+        match &self.instance {
+            Some(instance) if self.nested_instances => {
+                PathBuf::from(&self.game_name).join(instance)
+            }
+            Some(_) => PathBuf::from(self.session_name()),
+            None => PathBuf::from(&self.game_name),
         }
-        path
+    }
+
+    /// Select an instance of the game.
+    ///
+    /// Instances need to be declared in the `[instances.{game_name}]` section with a
+    /// `port_offset`, so their ports never change when other instances are added.
+    pub fn select_instance(&mut self, instance: Option<String>) -> Result<()> {
+        if let Some(instance) = &instance
+            && !self.game_instances().contains_key(instance)
+        {
+            bail!(
+                "Instance {instance} isn't declared in the [instances.{}] section of the config",
+                self.game_name
+            );
+        }
+        self.instance = instance;
+        // Fail early, if the instance's ports can't be determined.
+        self.port_offset()?;
+
+        Ok(())
+    }
+
+    /// All declared instances of this game.
+    fn game_instances(&self) -> BTreeMap<String, Instance> {
+        self.instances
+            .get(&self.game_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The overrides of the selected instance.
+    /// The default instance doesn't have any overrides.
+    pub fn instance_settings(&self) -> Instance {
+        self.instance
+            .as_ref()
+            .and_then(|instance| self.game_instances().remove(instance))
+            .unwrap_or_default()
+    }
+
+    /// The offset that's added to all ports of the selected instance.
+    pub fn port_offset(&self) -> Result<u16> {
+        let Some(instance) = &self.instance else {
+            return Ok(0);
+        };
+        match self.instance_settings().port_offset {
+            Some(0) | None => bail!(
+                "Instance {instance} needs a port_offset other than 0 in the [instances.{}] section",
+                self.game_name
+            ),
+            Some(offset) => Ok(offset),
+        }
+    }
+
+    /// Get a port of the game for the selected instance.
+    pub fn port(&self, default_port: u16) -> Result<u16> {
+        let offset = self.port_offset()?;
+        default_port.checked_add(offset).ok_or_else(|| {
+            eyre!(
+                "{} - Port {default_port} with the offset {offset} exceeds the highest port",
+                self.session_name()
+            )
+        })
+    }
+
+    /// How long the selected instance may take to become ready after startup.
+    pub fn ready_timeout(&self) -> Duration {
+        self.ready_timeouts
+            .get(&self.session_name())
+            .or_else(|| self.ready_timeouts.get(&self.game_name))
+            .map(|seconds| Duration::from_secs(*seconds))
+            .unwrap_or(DEFAULT_READY_TIMEOUT)
+    }
+
+    /// The password players need to join the selected instance.
+    pub fn password(&self) -> String {
+        self.instance_settings()
+            .password
+            .unwrap_or_else(|| self.default_password.clone())
+    }
+
+    /// The world, save or map that should be loaded by the selected instance.
+    pub fn world(&self, default_world: &str) -> String {
+        self.instance_settings()
+            .world
+            .unwrap_or_else(|| default_world.to_string())
     }
 
     pub fn game_dir(&self) -> PathBuf {
@@ -194,4 +343,71 @@ impl Config {
     pub fn temp_dir_str(&self) -> String {
         self.temp_dir().to_string_lossy().to_string()
     }
+
+    /// The location of the snapshot that's taken of the game files before each update.
+    pub fn snapshot_dir(&self) -> PathBuf {
+        expand(&self.temp_file_root)
+            .join("snapshots")
+            .join(self.game_subpath())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(extra: &str) -> Config {
+        toml::from_str(&format!(
+            r#"
+game_file_root = "/srv/games"
+backup_root = "/srv/backups"
+temp_file_root = "/srv/tmp"
+default_config_dir = "/srv/config"
+default_password = "hunter2"
+{extra}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn ready_timeouts() {
+        let mut config = config(
+            r#"
+[ready_timeouts]
+minecraft = 600
+minecraft-modpack = 900
+"#,
+        );
+        config.game_name = "minecraft".to_string();
+        assert_eq!(config.ready_timeout(), Duration::from_secs(600));
+        config.instance = Some("modpack".to_string());
+        assert_eq!(config.ready_timeout(), Duration::from_secs(900));
+
+        config.game_name = "factorio".to_string();
+        config.instance = None;
+        assert_eq!(config.ready_timeout(), DEFAULT_READY_TIMEOUT);
+    }
+
+    #[test]
+    fn instance_ports() {
+        let mut config = config(
+            r#"
+[instances.factorio.second]
+port_offset = 100
+
+[instances.factorio.third]
+"#,
+        );
+        config.game_name = "factorio".to_string();
+        assert_eq!(config.port(34197).unwrap(), 34197);
+
+        config.select_instance(Some("second".to_string())).unwrap();
+        assert_eq!(config.port(34197).unwrap(), 34297);
+        assert!(config.port(65500).is_err());
+
+        // Instances need an explicit offset.
+        assert!(config.select_instance(Some("third".to_string())).is_err());
+        assert!(config.select_instance(Some("fourth".to_string())).is_err());
+    }
 }
@@ -0,0 +1,32 @@
+use serde_derive::{Deserialize, Serialize};
+
+use super::Config;
+use crate::{errors::*, rcon::RconSettings};
+
+/// RCON settings for games that support it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Rcon {
+    /// The host the RCON server listens on.
+    #[serde(default = "default_host")]
+    pub host: String,
+    /// The port the RCON server listens on.
+    pub port: u16,
+    /// The RCON password. The password of the selected instance is used if none is set.
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl Rcon {
+    /// Get the settings to connect to the server.
+    /// The port offset of the selected instance is added to the configured port.
+    pub fn settings(&self, config: &Config) -> Result<RconSettings> {
+        Ok(RconSettings {
+            address: format!("{}:{}", self.host, config.port(self.port)?),
+            password: self.password.clone().unwrap_or_else(|| config.password()),
+        })
+    }
+}
+
+fn default_host() -> String {
+    "127.0.0.1".into()
+}
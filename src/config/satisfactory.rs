@@ -0,0 +1,46 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Satisfactory {
+    /// The url of the server's HTTPS API.
+    /// This only needs to be changed to test against a local server.
+    #[serde(default = "default_api_url")]
+    pub api_url: String,
+    /// The name of the server, which is set when claiming it.
+    #[serde(default = "default_server_name")]
+    pub server_name: String,
+    /// The admin password. The `default_password` is used if none is set.
+    #[serde(default)]
+    pub admin_password: Option<String>,
+    /// The password players need to join the server.
+    /// The server stays open for everyone if none is set.
+    #[serde(default)]
+    pub client_password: Option<String>,
+}
+
+impl Satisfactory {
+    pub fn admin_password(&self, default_password: &str) -> String {
+        self.admin_password
+            .clone()
+            .unwrap_or_else(|| default_password.to_string())
+    }
+}
+
+impl Default for Satisfactory {
+    fn default() -> Self {
+        Self {
+            api_url: default_api_url(),
+            server_name: default_server_name(),
+            admin_password: None,
+            client_password: None,
+        }
+    }
+}
+
+fn default_api_url() -> String {
+    "https://127.0.0.1:7777/api/v1".into()
+}
+
+fn default_server_name() -> String {
+    "Satisfactory".into()
+}
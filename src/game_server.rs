@@ -1,4 +1,13 @@
-use crate::{config::Config, errors::*};
+use std::time::Instant;
+
+use crate::{
+    config::Config,
+    errors::*,
+    query::Query,
+    rcon::RconSettings,
+    sleep_seconds,
+    snapshot::{create_snapshot, restore_snapshot},
+};
 
 /// Common trait for game server lifecycle management.
 ///
@@ -19,6 +28,103 @@ pub trait GameServer {
         self.config().session_name()
     }
 
+    /// Check whether the server is currently running.
+    ///
+    /// This is usually a passthrough to [crate::tmux::TmuxServer::is_session_open].
+    fn is_running(&self) -> Result<bool>;
+
+    /// Check whether the server is up and accepts connections.
+    ///
+    /// The default implementation has no way of telling and only checks whether the server is
+    /// running.
+    fn is_ready(&self) -> Result<bool> {
+        self.is_running()
+    }
+
+    /// Block until the server reports to be ready.
+    ///
+    /// Fails if the server doesn't become ready within the configured timeout.
+    fn wait_until_ready(&self) -> Result<()> {
+        let timeout = self.config().ready_timeout();
+        let start = Instant::now();
+        while !self.is_ready()? {
+            if start.elapsed() > timeout {
+                bail!(
+                    "{} - Server didn't become ready within {} seconds",
+                    self.session_name(),
+                    timeout.as_secs()
+                );
+            }
+            sleep_seconds(2);
+        }
+        info!("{} - Server is ready", self.session_name());
+
+        Ok(())
+    }
+
+    /// The settings to connect to the server via RCON, if it's configured.
+    ///
+    /// If RCON is configured, [crate::tmux::TmuxServer::send_command] prefers it over typing
+    /// into the tmux session.
+    fn rcon_settings(&self) -> Result<Option<RconSettings>> {
+        Ok(None)
+    }
+
+    /// The client for the server's query protocol, if the game supports one.
+    fn query_client(&self) -> Result<Option<Box<dyn Query>>> {
+        Ok(None)
+    }
+
+    /// Print the current status of the server.
+    ///
+    /// If the game supports a query protocol, live information such as the map and the players is
+    /// shown as well.
+    fn status(&self) -> Result<()> {
+        if !self.is_running()? {
+            println!("{} is not running", self.session_name());
+            return Ok(());
+        }
+
+        match self.query_client()? {
+            Some(client) => {
+                let info = client
+                    .query()
+                    .wrap_err(format!("{} - Failed to query server", self.session_name()))?;
+                println!("{} is running\n{info}", self.session_name());
+            }
+            None => println!("{} is running", self.session_name()),
+        }
+
+        Ok(())
+    }
+
+    /// Check whether the server is running without any players.
+    ///
+    /// This needs the server's query protocol, as it's the only way to know who's online.
+    fn is_idle(&self) -> Result<bool> {
+        if !self.is_running()? {
+            return Ok(false);
+        }
+        let Some(client) = self.query_client()? else {
+            bail!(
+                "{} - The server can't be queried for its players",
+                self.session_name()
+            );
+        };
+
+        let info = client
+            .query()
+            .wrap_err(format!("{} - Failed to query server", self.session_name()))?;
+        Ok(info.players == 0)
+    }
+
+    /// Paths relative to the game dir that contain server data, such as saves, configs or mods.
+    ///
+    /// These are excluded from the pre-update snapshot and are kept as they are during a rollback.
+    fn preserved_paths(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
     /// Start the game server.
     ///
     /// Wrapper around startup_inner with logging and other stuff.
@@ -56,18 +162,72 @@ pub trait GameServer {
         );
     }
 
-    /// Backup the game server.
+    /// Update the game server.
     ///
-    /// Wrapper around backup_inner with logging and other stuff.
+    /// Wrapper around update_inner, which takes care of the server's lifecycle:
+    /// 1. Shut down the server if it's running.
+    /// 2. Take a snapshot of the current installation.
+    /// 3. Update the server files.
+    /// 4. Start the server and wait for it to become ready. If it hasn't been running before, it's
+    ///    shut down again afterwards.
+    ///
+    /// If any of the last two steps fail, the installation is rolled back to the snapshot.
+    /// Fresh installations don't have a snapshot, so there's nothing to roll back.
     fn update(&self) -> Result<()> {
         info!("{} - Updating server", self.config().session_name());
-        self.update_inner()?;
+
+        let was_running = self.is_running()?;
+        if was_running {
+            self.shutdown().wrap_err("Failed during shutdown")?;
+            sleep_seconds(10);
+        }
+
+        let has_snapshot = create_snapshot(
+            &self.config().game_dir(),
+            &self.config().snapshot_dir(),
+            &self.preserved_paths(),
+        )
+        .wrap_err("Failed to create snapshot before update")?;
+
+        // The new installation has to start, even if the server hasn't been running before.
+        let result = self.update_inner().and_then(|_| {
+            self.startup()?;
+            self.wait_until_ready()
+        });
+        if let Err(error) = result {
+            if !has_snapshot {
+                if self.is_running()? {
+                    self.shutdown().wrap_err("Failed during shutdown")?;
+                }
+                return Err(error.wrap_err("Update failed, there's no snapshot to roll back to"));
+            }
+
+            error!(
+                "{} - Update failed, rolling back: {error:?}",
+                self.config().session_name()
+            );
+            if let Err(rollback_error) = self.restore_pre_update_snapshot(was_running) {
+                error!(
+                    "{} - Rollback failed: {rollback_error:?}",
+                    self.config().session_name()
+                );
+                return Err(error.wrap_err("Update failed and couldn't be rolled back"));
+            }
+
+            return Err(error.wrap_err("Update failed and has been rolled back"));
+        }
+
+        if !was_running {
+            self.shutdown().wrap_err("Failed during shutdown")?;
+        }
         info!("{} - Server has been updated", self.config().session_name());
 
         Ok(())
     }
 
-    /// Update the game server.
+    /// Update the game server files.
+    ///
+    /// The server is guaranteed to be stopped when this is called.
     fn update_inner(&self) -> Result<()> {
         bail!(
             "{} - Update functionality is not implemented",
@@ -75,6 +235,44 @@ pub trait GameServer {
         );
     }
 
+    /// Restore the installation from the snapshot that was taken before the last update.
+    ///
+    /// The server is only started again, if it has been running before.
+    fn rollback(&self) -> Result<()> {
+        self.restore_pre_update_snapshot(self.is_running()?)
+    }
+
+    /// Restore the installation from the pre-update snapshot and start the server afterwards,
+    /// if `restart` is set.
+    fn restore_pre_update_snapshot(&self, restart: bool) -> Result<()> {
+        info!(
+            "{} - Rolling back to the pre-update snapshot",
+            self.config().session_name()
+        );
+
+        if self.is_running()? {
+            self.shutdown().wrap_err("Failed during shutdown")?;
+            sleep_seconds(10);
+        }
+
+        restore_snapshot(
+            &self.config().snapshot_dir(),
+            &self.config().game_dir(),
+            &self.preserved_paths(),
+        )?;
+
+        if restart {
+            self.startup()?;
+            self.wait_until_ready()?;
+        }
+        info!(
+            "{} - Server has been rolled back",
+            self.config().session_name()
+        );
+
+        Ok(())
+    }
+
     /// Shutting down the game server.
     ///
     /// Wrapper around shutdown_inner with logging and other stuff.
@@ -89,6 +287,23 @@ pub trait GameServer {
         Ok(())
     }
 
+    /// Shut the server down, but only if nobody is playing on it.
+    fn shutdown_if_idle(&self) -> Result<()> {
+        if !self.is_running()? {
+            info!("{} - Server isn't running", self.session_name());
+            return Ok(());
+        }
+        if !self.is_idle()? {
+            info!(
+                "{} - Server isn't idle, keeping it running",
+                self.session_name()
+            );
+            return Ok(());
+        }
+
+        self.shutdown()
+    }
+
     /// Shutdown the game server gracefully.
     ///
     /// The default implementation uses Ctrl-C before exiting the tmux session. This works for most
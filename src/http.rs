@@ -0,0 +1,151 @@
+use std::{
+    fs::File,
+    io::{Read, copy},
+    path::Path,
+};
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::de::DeserializeOwned;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::errors::*;
+
+/// The characters that need to be encoded in a path segment.
+/// Everything besides the unreserved characters of RFC 3986.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Percent-encode a value, so it can be used as a single segment of an url's path.
+pub fn encode_path_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, PATH_SEGMENT).to_string()
+}
+
+/// Send a GET request and deserialize the JSON response.
+pub fn get_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    debug!("Requesting {url}");
+    ureq::get(url)
+        .call()
+        .wrap_err(format!("Request to {url} failed"))?
+        .body_mut()
+        .read_json()
+        .wrap_err(format!("Failed to deserialize response from {url}"))
+}
+
+/// Send a form encoded POST request and deserialize the JSON response.
+pub fn post_form_json<T: DeserializeOwned>(url: &str, form: &[(String, String)]) -> Result<T> {
+    debug!("Posting to {url}");
+    ureq::post(url)
+        .send_form(
+            form.iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )
+        .wrap_err(format!("Request to {url} failed"))?
+        .body_mut()
+        .read_json()
+        .wrap_err(format!("Failed to deserialize response from {url}"))
+}
+
+/// Send a GET request and return the response as text.
+pub fn get_text(url: &str) -> Result<String> {
+    debug!("Requesting {url}");
+    ureq::get(url)
+        .call()
+        .wrap_err(format!("Request to {url} failed"))?
+        .body_mut()
+        .read_to_string()
+        .wrap_err(format!("Failed to read response from {url}"))
+}
+
+/// Download a file to the given destination.
+///
+/// The file is streamed to disk, so this also works for large files.
+pub fn download(url: &str, dest: &Path) -> Result<()> {
+    download_with_query(url, &[], dest)
+}
+
+/// Download a file to the given destination with additional query parameters.
+///
+/// The parameters are encoded and never show up in logs or errors, which makes them suitable
+/// for credentials.
+pub fn download_with_query(url: &str, query: &[(&str, &str)], dest: &Path) -> Result<()> {
+    info!("Downloading {url} to {dest:?}");
+    let mut response = ureq::get(url)
+        .query_pairs(query.iter().copied())
+        .call()
+        .wrap_err(format!("Request to {url} failed"))?;
+
+    let mut file = File::create(dest).wrap_err(format!("Failed to create file {dest:?}"))?;
+    copy(&mut response.body_mut().as_reader(), &mut file)
+        .wrap_err(format!("Failed to download {url} to {dest:?}"))?;
+
+    Ok(())
+}
+
+/// Calculate the hex encoded sha256 sum of a file.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    hash_file::<Sha256>(path)
+}
+
+/// Calculate the hex encoded sha1 sum of a file.
+pub fn sha1_file(path: &Path) -> Result<String> {
+    hash_file::<Sha1>(path)
+}
+
+fn hash_file<D: Digest>(path: &Path) -> Result<String> {
+    let mut file = File::open(path).wrap_err(format!("Failed to open file {path:?}"))?;
+    let mut hasher = D::new();
+
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .wrap_err(format!("Failed to read file {path:?}"))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{remove_file, write};
+
+    use super::*;
+
+    #[test]
+    fn file_checksums() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("http-checksum-test-{}", std::process::id()));
+        write(&path, "hello world\n")?;
+
+        let sha256 = sha256_file(&path);
+        let sha1 = sha1_file(&path);
+        remove_file(&path)?;
+
+        assert_eq!(
+            sha256?,
+            "a948904f2f0f479b8f8197694b30184b0d2ed1c1cd2a1ec0fb85d299a192a447"
+        );
+        assert_eq!(sha1?, "22596363b3de40b06f981fb85d82312e8c0ed511");
+
+        Ok(())
+    }
+
+    #[test]
+    fn checksum_of_missing_file() {
+        assert!(sha256_file(Path::new("/nonexistent/file")).is_err());
+    }
+
+    #[test]
+    fn encode_path_segments() {
+        assert_eq!(encode_path_segment("Krastorio2"), "Krastorio2");
+        assert_eq!(encode_path_segment("Squeak Through"), "Squeak%20Through");
+        assert_eq!(encode_path_segment("a/b?c#d"), "a%2Fb%3Fc%23d");
+    }
+}
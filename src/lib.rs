@@ -3,11 +3,20 @@ use std::time::Duration;
 pub mod backup;
 pub mod config;
 pub mod game_server;
+pub mod http;
 pub mod log;
+pub mod network;
 pub mod path;
 pub mod process;
+pub mod properties;
+pub mod query;
+pub mod rcon;
 pub mod secret;
+pub mod snapshot;
+pub mod source;
+pub mod steam;
 pub mod tmux;
+pub mod workshop;
 pub mod zellij;
 pub fn sleep_seconds(seconds: u64) {
     let duration = Duration::from_secs(seconds);
@@ -22,11 +31,25 @@ pub mod prelude {
         errors::*,
         game_server::GameServer,
         log::install_tracing,
+        network::*,
         path::*,
         process::*,
-        secret::copy_secret_file,
+        properties::Properties,
+        query::{
+            Query,
+            ServerInfo,
+            a2s::A2sClient,
+            gamespy::GameSpyClient,
+            minecraft::MinecraftClient,
+            quake3::Quake3Client,
+        },
+        rcon::{RconClient, RconSettings},
+        secret::{copy_secret_file, render_secret_file},
         sleep_seconds,
+        source::{MapLocation, SourceServer},
+        steam::SteamServer,
         tmux::*,
+        workshop::{WorkshopItem, WorkshopReport},
     };
 }
 #[allow(unused_imports)]
@@ -0,0 +1,69 @@
+use strum_macros::Display;
+
+use crate::{cmd, errors::*, process::*};
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// Check whether some process on this machine listens on the given port.
+///
+/// This is used as a simple readiness check, as most servers only bind their ports once they're
+/// done loading.
+pub fn is_port_bound(port: u16, protocol: Protocol) -> Result<bool> {
+    let output = list_sockets(port, protocol, "")?;
+    Ok(!output.trim().is_empty())
+}
+
+/// Get the ids of all processes that listen on the given port.
+///
+/// Processes of other users are only visible with root privileges.
+pub fn port_owners(port: u16, protocol: Protocol) -> Result<Vec<u32>> {
+    let output = list_sockets(port, protocol, "-p")?;
+    Ok(parse_socket_pids(&output))
+}
+
+fn list_sockets(port: u16, protocol: Protocol, extra_flags: &str) -> Result<String> {
+    let flag = match protocol {
+        Protocol::Tcp => "-t",
+        Protocol::Udp => "-u",
+    };
+
+    let output = cmd!("ss -H -l -n {flag} {extra_flags} 'sport = :{port}'")
+        .run_success()
+        .wrap_err(format!(
+            "Failed to check whether {protocol} port {port} is bound"
+        ))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Extract the process ids from the output of `ss -p`,
+/// e.g. `users:(("srcds_linux",pid=1234,fd=5))`.
+fn parse_socket_pids(output: &str) -> Vec<u32> {
+    let mut pids = Vec::new();
+    for part in output.split("pid=").skip(1) {
+        let digits: String = part
+            .chars()
+            .take_while(|char| char.is_ascii_digit())
+            .collect();
+        if let Ok(pid) = digits.parse()
+            && !pids.contains(&pid)
+        {
+            pids.push(pid);
+        }
+    }
+    pids
+}
+
+/// Get the id of a process' parent, if the process exists.
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The process name is wrapped in parenthesis and may contain spaces.
+    // The state and the parent's id follow right after it.
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
@@ -0,0 +1,209 @@
+use std::{
+    fs::{read_to_string, write},
+    path::Path,
+};
+
+use crate::errors::*;
+
+#[derive(Clone, Debug)]
+enum Line {
+    Entry {
+        key: String,
+        value: String,
+    },
+    /// Comments, empty lines and anything else that isn't a key/value pair.
+    Other(String),
+}
+
+/// A Java `.properties` file, as it's used by Minecraft's `server.properties`.
+///
+/// Comments and the order of keys are kept as they are, so files can be edited without losing
+/// any manual changes.
+/// Values are stored raw, i.e. escape sequences aren't interpreted.
+#[derive(Clone, Debug, Default)]
+pub struct Properties {
+    lines: Vec<Line>,
+}
+
+impl Properties {
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                    return Line::Other(line.to_string());
+                }
+
+                match trimmed.split_once(['=', ':']) {
+                    Some((key, value)) => Line::Entry {
+                        key: key.trim().to_string(),
+                        value: value.trim_start().to_string(),
+                    },
+                    None => Line::Entry {
+                        key: trimmed.trim().to_string(),
+                        value: String::new(),
+                    },
+                }
+            })
+            .collect();
+
+        Properties { lines }
+    }
+
+    /// Read a properties file. A missing file results in empty properties.
+    pub fn read(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Properties::default());
+        }
+
+        let content = read_to_string(path).wrap_err(format!("Failed to read {path:?}"))?;
+        Ok(Properties::parse(&content))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        write(path, self.to_string()).wrap_err(format!("Failed to write {path:?}"))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Entry { key: name, value } if name == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Set a value. Existing keys are updated in place, new keys are appended.
+    pub fn set(&mut self, key: &str, value: &str) {
+        for line in self.lines.iter_mut() {
+            if let Line::Entry {
+                key: name,
+                value: old,
+            } = line
+                && name == key
+            {
+                *old = value.to_string();
+                return;
+            }
+        }
+
+        self.lines.push(Line::Entry {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    /// Set all values of the other properties on these properties.
+    /// Keys that only exist in these properties are kept.
+    pub fn merge(&mut self, other: &Properties) {
+        for line in &other.lines {
+            if let Line::Entry { key, value } = line {
+                self.set(key, value);
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Properties {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Entry { key, value } => writeln!(f, "{key}={value}")?,
+                Line::Other(line) => writeln!(f, "{line}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "#Minecraft server properties
+#Sat Jan 04 12:00:00 CET 2025
+motd=A Minecraft Server
+server-port = 25565
+! another comment
+
+level-name: world
+white-list
+";
+
+    #[test]
+    fn parse() {
+        let properties = Properties::parse(CONTENT);
+        assert_eq!(properties.get("motd"), Some("A Minecraft Server"));
+        assert_eq!(properties.get("server-port"), Some("25565"));
+        assert_eq!(properties.get("level-name"), Some("world"));
+        assert_eq!(properties.get("white-list"), Some(""));
+        assert_eq!(properties.get("max-players"), None);
+        // Comments aren't keys.
+        assert_eq!(properties.get("#Minecraft server properties"), None);
+    }
+
+    #[test]
+    fn keeps_comments_and_order() {
+        let properties = Properties::parse(CONTENT);
+        assert_eq!(
+            properties.to_string(),
+            "#Minecraft server properties
+#Sat Jan 04 12:00:00 CET 2025
+motd=A Minecraft Server
+server-port=25565
+! another comment
+
+level-name=world
+white-list=
+"
+        );
+    }
+
+    #[test]
+    fn set_and_merge() {
+        let mut properties = Properties::parse(CONTENT);
+        properties.set("server-port", "25566");
+        properties.set("max-players", "10");
+        assert_eq!(properties.get("server-port"), Some("25566"));
+        assert!(
+            properties
+                .to_string()
+                .contains("server-port=25566\n! another comment")
+        );
+        assert!(
+            properties
+                .to_string()
+                .ends_with("white-list=\nmax-players=10\n")
+        );
+
+        let mut declared = Properties::default();
+        declared.set("motd", "Survival");
+        declared.set("white-list", "true");
+        properties.merge(&declared);
+        assert_eq!(properties.get("motd"), Some("Survival"));
+        assert_eq!(properties.get("white-list"), Some("true"));
+        // Keys that aren't declared are kept.
+        assert_eq!(properties.get("level-name"), Some("world"));
+        assert_eq!(properties.get("max-players"), Some("10"));
+    }
+
+    #[test]
+    fn read_and_write() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("properties-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("server.properties");
+
+        // A missing file has no properties.
+        assert!(Properties::read(&path)?.get("motd").is_none());
+
+        let mut properties = Properties::parse(CONTENT);
+        properties.set("motd", "Written");
+        properties.write(&path)?;
+        let read = Properties::read(&path)?;
+        assert_eq!(read.get("motd"), Some("Written"));
+        assert_eq!(read.to_string(), properties.to_string());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
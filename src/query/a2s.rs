@@ -0,0 +1,404 @@
+use std::{collections::BTreeMap, net::UdpSocket, time::Duration};
+
+use super::{Query, Reader, ServerInfo};
+use crate::errors::*;
+
+/// How long to wait for a response.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum size of a single UDP packet sent by Source servers.
+const MAX_PACKET_SIZE: usize = 1400;
+
+const SINGLE_PACKET: i32 = -1;
+const SPLIT_PACKET: i32 = -2;
+
+const A2S_INFO: u8 = 0x54;
+const A2S_PLAYER: u8 = 0x55;
+const A2S_RULES: u8 = 0x56;
+const S2A_INFO: u8 = 0x49;
+const S2A_PLAYER: u8 = 0x44;
+const S2A_RULES: u8 = 0x45;
+const S2C_CHALLENGE: u8 = 0x41;
+
+/// The response to an A2S_INFO request.
+#[derive(Clone, Debug)]
+pub struct A2sInfo {
+    pub protocol: u8,
+    pub name: String,
+    pub map: String,
+    pub folder: String,
+    pub game: String,
+    pub app_id: i16,
+    pub players: u8,
+    pub max_players: u8,
+    pub bots: u8,
+    pub password_protected: bool,
+    pub vac_secured: bool,
+    pub version: String,
+}
+
+/// A single entry of the response to an A2S_PLAYER request.
+#[derive(Clone, Debug)]
+pub struct A2sPlayer {
+    pub name: String,
+    pub score: i32,
+    /// How long the player has been connected, in seconds.
+    pub duration: f32,
+}
+
+/// A client for Valve's A2S server query protocol.
+///
+/// The protocol is spoken by Source engine games and by most games that use the Steam server
+/// API, usually on a dedicated query port.
+/// See https://developer.valvesoftware.com/wiki/Server_queries
+pub struct A2sClient {
+    /// The address of the query port, e.g. `127.0.0.1:27015`.
+    address: String,
+}
+
+impl A2sClient {
+    pub fn new(address: impl ToString) -> Self {
+        A2sClient {
+            address: address.to_string(),
+        }
+    }
+
+    /// Query general information about the server, such as its name, map and player count.
+    pub fn info(&self) -> Result<A2sInfo> {
+        let response = self.request(A2S_INFO, b"Source Engine Query\0", false)?;
+
+        let mut reader = Reader::new(&response);
+        if reader.u8()? != S2A_INFO {
+            bail!("Got unexpected response to A2S_INFO");
+        }
+
+        let protocol = reader.u8()?;
+        let name = reader.string()?;
+        let map = reader.string()?;
+        let folder = reader.string()?;
+        let game = reader.string()?;
+        let app_id = reader.i16()?;
+        let players = reader.u8()?;
+        let max_players = reader.u8()?;
+        let bots = reader.u8()?;
+        let _server_type = reader.u8()?;
+        let _environment = reader.u8()?;
+        let password_protected = reader.u8()? == 1;
+        let vac_secured = reader.u8()? == 1;
+        let version = reader.string()?;
+
+        Ok(A2sInfo {
+            protocol,
+            name,
+            map,
+            folder,
+            game,
+            app_id,
+            players,
+            max_players,
+            bots,
+            password_protected,
+            vac_secured,
+            version,
+        })
+    }
+
+    /// Query the list of players that're currently connected.
+    pub fn players(&self) -> Result<Vec<A2sPlayer>> {
+        let response = self.request(A2S_PLAYER, &[], true)?;
+
+        let mut reader = Reader::new(&response);
+        if reader.u8()? != S2A_PLAYER {
+            bail!("Got unexpected response to A2S_PLAYER");
+        }
+
+        let count = reader.u8()?;
+        let mut players = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let _index = reader.u8()?;
+            players.push(A2sPlayer {
+                name: reader.string()?,
+                score: reader.i32()?,
+                duration: reader.f32()?,
+            });
+        }
+
+        Ok(players)
+    }
+
+    /// Query the server's rules, i.e. its public console variables.
+    pub fn rules(&self) -> Result<BTreeMap<String, String>> {
+        let response = self.request(A2S_RULES, &[], true)?;
+
+        let mut reader = Reader::new(&response);
+        if reader.u8()? != S2A_RULES {
+            bail!("Got unexpected response to A2S_RULES");
+        }
+
+        let count = reader.i16()?;
+        let mut rules = BTreeMap::new();
+        for _ in 0..count {
+            // Some servers announce more rules than they actually send.
+            if reader.remaining() == 0 {
+                break;
+            }
+            rules.insert(reader.string()?, reader.string()?);
+        }
+
+        Ok(rules)
+    }
+
+    /// Send a request and return the payload of the response.
+    ///
+    /// Servers may answer with a challenge number first, in which case the request has to be sent
+    /// again with the challenge appended. A2S_PLAYER and A2S_RULES always expect a challenge,
+    /// which is why `with_challenge` sends an initial `-1` challenge for them.
+    fn request(&self, kind: u8, payload: &[u8], with_challenge: bool) -> Result<Vec<u8>> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(TIMEOUT))?;
+        socket
+            .connect(&self.address)
+            .wrap_err(format!("Failed to connect to {}", self.address))?;
+
+        let mut challenge = with_challenge.then(|| (-1i32).to_le_bytes().to_vec());
+
+        // Servers may send a few challenges in a row, but there's no point in trying forever.
+        for _ in 0..3 {
+            let mut packet = SINGLE_PACKET.to_le_bytes().to_vec();
+            packet.push(kind);
+            packet.extend_from_slice(payload);
+            if let Some(challenge) = &challenge {
+                packet.extend_from_slice(challenge);
+            }
+
+            socket
+                .send(&packet)
+                .wrap_err(format!("Failed to send query to {}", self.address))?;
+            let response = receive(&socket).wrap_err(format!(
+                "Failed to receive query response from {}",
+                self.address
+            ))?;
+
+            if response.first() != Some(&S2C_CHALLENGE) {
+                return Ok(response);
+            }
+            challenge = Some(Reader::new(&response[1..]).bytes(4)?.to_vec());
+        }
+
+        bail!("Server {} kept answering with challenges", self.address);
+    }
+}
+
+/// Receive a full response, which might be split into multiple packets.
+/// Returns the response without the leading single packet header.
+fn receive(socket: &UdpSocket) -> Result<Vec<u8>> {
+    let mut buffer = [0; MAX_PACKET_SIZE * 2];
+    let size = socket.recv(&mut buffer)?;
+    let mut reader = Reader::new(&buffer[..size]);
+
+    match reader.i32()? {
+        SINGLE_PACKET => Ok(reader.bytes(reader.remaining())?.to_vec()),
+        SPLIT_PACKET => receive_split(socket, &buffer[..size]),
+        header => bail!("Got response with invalid header {header}"),
+    }
+}
+
+/// Collect all packets of a split response and assemble them.
+fn receive_split(socket: &UdpSocket, first: &[u8]) -> Result<Vec<u8>> {
+    let mut parts: BTreeMap<u8, Vec<u8>> = BTreeMap::new();
+    let mut packet = first.to_vec();
+
+    loop {
+        let mut reader = Reader::new(&packet);
+        let _header = reader.i32()?;
+        let id = reader.i32()?;
+        if id as u32 & 0x8000_0000 != 0 {
+            bail!("Compressed split responses aren't supported");
+        }
+        let total = reader.u8()?;
+        let number = reader.u8()?;
+        let _size = reader.i16()?;
+        parts.insert(number, reader.bytes(reader.remaining())?.to_vec());
+
+        if parts.len() == total as usize {
+            break;
+        }
+
+        let mut buffer = [0; MAX_PACKET_SIZE * 2];
+        let size = socket.recv(&mut buffer)?;
+        packet = buffer[..size].to_vec();
+    }
+
+    // The assembled payload starts with the single packet header.
+    let payload: Vec<u8> = parts.into_values().flatten().collect();
+    let mut reader = Reader::new(&payload);
+    if reader.i32()? != SINGLE_PACKET {
+        bail!("Assembled split response has an invalid header");
+    }
+
+    Ok(reader.bytes(reader.remaining())?.to_vec())
+}
+
+impl Query for A2sClient {
+    fn query(&self) -> Result<ServerInfo> {
+        let info = self.info()?;
+        // Not all servers answer player requests, so we don't fail the whole query for it.
+        let player_names = match self.players() {
+            Ok(players) => players
+                .into_iter()
+                .map(|player| player.name)
+                .filter(|name| !name.is_empty())
+                .collect(),
+            Err(error) => {
+                debug!("Failed to query players: {error:?}");
+                Vec::new()
+            }
+        };
+
+        Ok(ServerInfo {
+            name: info.name,
+            map: Some(info.map).filter(|map| !map.is_empty()),
+            version: Some(info.version).filter(|version| !version.is_empty()),
+            players: info.players as u32,
+            max_players: info.max_players as u32,
+            player_names,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::tests::serve;
+
+    fn single(payload: &[u8]) -> Vec<u8> {
+        let mut packet = SINGLE_PACKET.to_le_bytes().to_vec();
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn info_payload() -> Vec<u8> {
+        let mut payload = vec![S2A_INFO, 17];
+        for string in [
+            "TTT Server",
+            "ttt_minecraft_b5",
+            "garrysmod",
+            "Trouble in Terrorist Town",
+        ] {
+            payload.extend_from_slice(string.as_bytes());
+            payload.push(0);
+        }
+        payload.extend_from_slice(&4000i16.to_le_bytes());
+        // Players, max players, bots, server type, environment, visibility, VAC
+        payload.extend_from_slice(&[3, 16, 1, b'd', b'l', 1, 0]);
+        payload.extend_from_slice(b"2024.12.04\0");
+        payload
+    }
+
+    #[test]
+    fn info_with_challenge() -> Result<()> {
+        let (address, server) = serve(2, |request| {
+            if request.len() == 25 {
+                // The first request doesn't contain a challenge yet.
+                let mut challenge = vec![S2C_CHALLENGE];
+                challenge.extend_from_slice(&0x1234_5678i32.to_le_bytes());
+                vec![single(&challenge)]
+            } else {
+                vec![single(&info_payload())]
+            }
+        });
+
+        let info = A2sClient::new(address).info()?;
+        assert_eq!(info.name, "TTT Server");
+        assert_eq!(info.map, "ttt_minecraft_b5");
+        assert_eq!(info.folder, "garrysmod");
+        assert_eq!(info.app_id, 4000);
+        assert_eq!((info.players, info.max_players, info.bots), (3, 16, 1));
+        assert!(info.password_protected);
+        assert!(!info.vac_secured);
+        assert_eq!(info.version, "2024.12.04");
+
+        // The challenge is appended to the repeated request.
+        let requests = server.join().unwrap();
+        assert_eq!(&requests[0][..5], &[0xff, 0xff, 0xff, 0xff, A2S_INFO]);
+        assert_eq!(&requests[1][25..], &0x1234_5678i32.to_le_bytes());
+
+        Ok(())
+    }
+
+    #[test]
+    fn players_in_split_response() -> Result<()> {
+        let mut payload = single(&[S2A_PLAYER, 2]);
+        for (name, score) in [("Alice", 12i32), ("Bob", -1)] {
+            payload.push(0);
+            payload.extend_from_slice(name.as_bytes());
+            payload.push(0);
+            payload.extend_from_slice(&score.to_le_bytes());
+            payload.extend_from_slice(&90.5f32.to_le_bytes());
+        }
+
+        let (address, server) = serve(1, move |request| {
+            // A2S_PLAYER is always sent with a challenge.
+            assert_eq!(request[4], A2S_PLAYER);
+            assert_eq!(&request[5..], &(-1i32).to_le_bytes());
+
+            // The parts are sent in reverse order to make sure that they're reassembled.
+            let (first, second) = payload.split_at(10);
+            [(1u8, second), (0u8, first)]
+                .into_iter()
+                .map(|(number, part)| {
+                    let mut packet = SPLIT_PACKET.to_le_bytes().to_vec();
+                    packet.extend_from_slice(&42i32.to_le_bytes());
+                    packet.extend_from_slice(&[2, number]);
+                    packet.extend_from_slice(&(MAX_PACKET_SIZE as i16).to_le_bytes());
+                    packet.extend_from_slice(part);
+                    packet
+                })
+                .collect()
+        });
+
+        let players = A2sClient::new(address).players()?;
+        server.join().unwrap();
+
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].name, "Alice");
+        assert_eq!(players[0].score, 12);
+        assert_eq!(players[1].name, "Bob");
+        assert_eq!(players[1].score, -1);
+        assert_eq!(players[1].duration, 90.5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rules_with_missing_entries() -> Result<()> {
+        let mut payload = vec![S2A_RULES];
+        payload.extend_from_slice(&3i16.to_le_bytes());
+        payload.extend_from_slice(b"sv_gravity\x00600\x00mp_friendlyfire\x001\x00");
+
+        let (address, server) = serve(1, move |_| vec![single(&payload)]);
+        let rules = A2sClient::new(address).rules()?;
+        server.join().unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules["sv_gravity"], "600");
+        assert_eq!(rules["mp_friendlyfire"], "1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_responses() {
+        let (address, server) = serve(1, |_| vec![vec![0, 0, 0, 0, S2A_INFO]]);
+        assert!(A2sClient::new(address).info().is_err());
+        server.join().unwrap();
+
+        let (address, server) = serve(1, |_| vec![single(&[S2A_PLAYER])]);
+        assert!(A2sClient::new(address).info().is_err());
+        server.join().unwrap();
+
+        let (address, server) = serve(1, |_| vec![single(&[S2A_INFO, 17, b'x'])]);
+        assert!(A2sClient::new(address).info().is_err());
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+use std::{collections::BTreeMap, net::UdpSocket, time::Duration};
+
+use super::{Query, ServerInfo};
+use crate::errors::*;
+
+/// How long to wait for a response.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum size of a single response packet.
+const MAX_PACKET_SIZE: usize = 2048;
+
+/// A client for the original GameSpy query protocol.
+///
+/// The protocol is spoken by Unreal engine games such as UT2004, usually on the game port + 10.
+/// Responses are `\key\value` pairs, which may be split over several packets.
+/// Each packet carries a `queryid` of the form `{id}.{number}` and the last one contains `final`.
+pub struct GameSpyClient {
+    /// The address of the query port, e.g. `127.0.0.1:7787`.
+    address: String,
+}
+
+impl GameSpyClient {
+    pub fn new(address: impl ToString) -> Self {
+        GameSpyClient {
+            address: address.to_string(),
+        }
+    }
+
+    /// Send a query such as `status` and return all received key/value pairs.
+    ///
+    /// Player specific keys are suffixed with the player's index, e.g. `player_0`.
+    pub fn request(&self, query: &str) -> Result<BTreeMap<String, String>> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(TIMEOUT))?;
+        socket
+            .connect(&self.address)
+            .wrap_err(format!("Failed to connect to {}", self.address))?;
+        socket
+            .send(format!("\\{query}\\").as_bytes())
+            .wrap_err(format!("Failed to send query to {}", self.address))?;
+
+        // Packets may arrive out of order, so they're sorted by their number.
+        let mut packets: BTreeMap<u32, Vec<(String, String)>> = BTreeMap::new();
+        let mut total = None;
+        while total != Some(packets.len()) {
+            let mut buffer = [0; MAX_PACKET_SIZE];
+            let size = socket.recv(&mut buffer).wrap_err(format!(
+                "Failed to receive query response from {}",
+                self.address
+            ))?;
+
+            let mut number = packets.len() as u32 + 1;
+            let mut pairs = Vec::new();
+            let mut is_final = false;
+            for (key, value) in parse_pairs(&String::from_utf8_lossy(&buffer[..size])) {
+                match key.as_str() {
+                    "final" => is_final = true,
+                    "queryid" => {
+                        if let Some(parsed) = value
+                            .split_once('.')
+                            .and_then(|(_, number)| number.parse().ok())
+                        {
+                            number = parsed;
+                        }
+                    }
+                    _ => pairs.push((key, value)),
+                }
+            }
+
+            if is_final {
+                total = Some(number as usize);
+            }
+            packets.insert(number, pairs);
+        }
+
+        Ok(packets.into_values().flatten().collect())
+    }
+}
+
+impl Query for GameSpyClient {
+    fn query(&self) -> Result<ServerInfo> {
+        let values = self.request("status")?;
+        let value = |name: &str| values.get(name).filter(|value| !value.is_empty());
+
+        let mut player_names: Vec<(usize, String)> = values
+            .iter()
+            .filter_map(|(key, name)| {
+                let index = key.strip_prefix("player_")?.parse().ok()?;
+                Some((index, name.clone()))
+            })
+            .collect();
+        player_names.sort();
+
+        Ok(ServerInfo {
+            name: value("hostname").cloned().unwrap_or_default(),
+            map: value("mapname").cloned(),
+            version: value("gamever").cloned(),
+            players: value("numplayers")
+                .and_then(|players| players.parse().ok())
+                .unwrap_or(player_names.len() as u32),
+            max_players: value("maxplayers")
+                .and_then(|max| max.parse().ok())
+                .unwrap_or_default(),
+            player_names: player_names.into_iter().map(|(_, name)| name).collect(),
+        })
+    }
+}
+
+/// Parse a response in the format `\key\value\key\value`.
+///
+/// The `final` marker doesn't have a value, so it's returned with an empty one.
+fn parse_pairs(response: &str) -> Vec<(String, String)> {
+    let mut parts = response.strip_prefix('\\').unwrap_or(response).split('\\');
+    let mut pairs = Vec::new();
+    while let Some(key) = parts.next() {
+        if key.is_empty() {
+            continue;
+        }
+        if key == "final" {
+            pairs.push((key.to_string(), String::new()));
+            continue;
+        }
+        pairs.push((
+            key.to_string(),
+            parts.next().unwrap_or_default().to_string(),
+        ));
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::tests::serve;
+
+    #[test]
+    fn pairs() {
+        assert_eq!(
+            parse_pairs("\\hostname\\My Server\\numplayers\\2\\final\\"),
+            vec![
+                ("hostname".to_string(), "My Server".to_string()),
+                ("numplayers".to_string(), "2".to_string()),
+                ("final".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(
+            parse_pairs("\\password\\\\final\\"),
+            vec![
+                ("password".to_string(), String::new()),
+                ("final".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn status_in_multiple_packets() -> Result<()> {
+        let (address, server) = serve(1, |request| {
+            assert_eq!(request, b"\\status\\");
+            // The packets arrive out of order.
+            vec![
+                b"\\player_1\\Bob\\queryid\\42.2\\final\\".to_vec(),
+                b"\\hostname\\Arena Master\\gamever\\3369\\mapname\\DM-Asbestos\\numplayers\\2\\maxplayers\\16\\player_0\\Alice\\queryid\\42.1".to_vec(),
+            ]
+        });
+
+        let info = GameSpyClient::new(address).query()?;
+        server.join().unwrap();
+
+        assert_eq!(info.name, "Arena Master");
+        assert_eq!(info.map.as_deref(), Some("DM-Asbestos"));
+        assert_eq!(info.version.as_deref(), Some("3369"));
+        assert_eq!((info.players, info.max_players), (2, 16));
+        assert_eq!(info.player_names, vec!["Alice", "Bob"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_without_player_count() -> Result<()> {
+        let (address, server) = serve(1, |_| {
+            vec![b"\\hostname\\Empty\\player_0\\Alice\\queryid\\7.1\\final\\".to_vec()]
+        });
+
+        let info = GameSpyClient::new(address).query()?;
+        server.join().unwrap();
+
+        assert_eq!(info.players, 1);
+        assert_eq!(info.map, None);
+
+        Ok(())
+    }
+}
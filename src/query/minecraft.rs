@@ -0,0 +1,402 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+use super::{Query, ServerInfo};
+use crate::errors::*;
+
+/// How long to wait for a connection or a response.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum size of a status response we're willing to read.
+const MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+/// The JSON response to a status request.
+#[derive(Debug, Deserialize)]
+struct StatusResponse {
+    version: Version,
+    players: Players,
+    /// Either a plain string or a chat component.
+    #[serde(default)]
+    description: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Version {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Players {
+    max: u32,
+    online: u32,
+    #[serde(default)]
+    sample: Vec<PlayerSample>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayerSample {
+    name: String,
+}
+
+/// A client for Minecraft's Server List Ping protocol.
+///
+/// The modern (1.7+) handshake/status protocol is tried first.
+/// If that fails, the legacy ping of older servers is used as a fallback.
+/// See https://minecraft.wiki/w/Java_Edition_protocol/Server_List_Ping
+pub struct MinecraftClient {
+    host: String,
+    port: u16,
+}
+
+impl MinecraftClient {
+    pub fn new(host: impl ToString, port: u16) -> Self {
+        MinecraftClient {
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    fn connect(&self) -> Result<TcpStream> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .wrap_err(format!("Failed to resolve {}", self.host))?
+            .next()
+            .ok_or_else(|| eyre!("Couldn't resolve {}", self.host))?;
+
+        let stream = TcpStream::connect_timeout(&address, TIMEOUT)
+            .wrap_err(format!("Failed to connect to {address}"))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        Ok(stream)
+    }
+
+    /// Query the server via the modern handshake/status protocol.
+    pub fn status(&self) -> Result<ServerInfo> {
+        let mut stream = self.connect()?;
+
+        // Handshake with protocol version -1 and next state 1 (status).
+        let mut handshake = Vec::new();
+        write_varint(&mut handshake, 0x00);
+        write_varint(&mut handshake, -1);
+        write_string(&mut handshake, &self.host);
+        handshake.extend_from_slice(&self.port.to_be_bytes());
+        write_varint(&mut handshake, 1);
+        send_packet(&mut stream, &handshake)?;
+
+        // Status request, which is an empty packet with id 0.
+        send_packet(&mut stream, &[0x00])?;
+
+        let length = read_varint(&mut stream)? as usize;
+        if length > MAX_RESPONSE_SIZE {
+            bail!("Status response is too large: {length} bytes");
+        }
+        let mut packet = vec![0; length];
+        stream
+            .read_exact(&mut packet)
+            .wrap_err("Failed to read status response")?;
+
+        let mut packet = packet.as_slice();
+        if read_varint(&mut packet)? != 0x00 {
+            bail!("Got unexpected packet in response to status request");
+        }
+        let json_length = read_varint(&mut packet)? as usize;
+        let json = packet
+            .get(..json_length)
+            .ok_or_else(|| eyre!("Status response is truncated"))?;
+
+        let response: StatusResponse =
+            serde_json::from_slice(json).wrap_err("Failed to parse status response")?;
+
+        Ok(ServerInfo {
+            name: chat_to_text(&response.description),
+            map: None,
+            version: Some(response.version.name),
+            players: response.players.online,
+            max_players: response.players.max,
+            player_names: response
+                .players
+                .sample
+                .into_iter()
+                .map(|player| player.name)
+                .collect(),
+        })
+    }
+
+    /// Query the server via the legacy ping, which is understood by servers older than 1.7.
+    pub fn legacy_ping(&self) -> Result<ServerInfo> {
+        let mut stream = self.connect()?;
+        stream.write_all(&[0xFE, 0x01])?;
+
+        let mut header = [0; 3];
+        stream
+            .read_exact(&mut header)
+            .wrap_err("Failed to read legacy ping response")?;
+        if header[0] != 0xFF {
+            bail!("Got unexpected response to legacy ping");
+        }
+
+        // The response is a UTF-16BE string, prefixed with its length in characters.
+        let length = u16::from_be_bytes([header[1], header[2]]) as usize;
+        let mut data = vec![0; length * 2];
+        stream.read_exact(&mut data)?;
+        let chars: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+            .collect();
+        let response = String::from_utf16_lossy(&chars);
+
+        // 1.4+ servers respond with `§1\0{protocol}\0{version}\0{motd}\0{online}\0{max}`.
+        // Older servers respond with `{motd}§{online}§{max}`.
+        let (name, version, online, max) = if let Some(rest) = response.strip_prefix("§1\0") {
+            let fields: Vec<&str> = rest.split('\0').collect();
+            if fields.len() < 5 {
+                bail!("Got malformed legacy ping response");
+            }
+            (fields[2], Some(fields[1]), fields[3], fields[4])
+        } else {
+            let fields: Vec<&str> = response.split('§').collect();
+            if fields.len() < 3 {
+                bail!("Got malformed legacy ping response");
+            }
+            (fields[0], None, fields[1], fields[2])
+        };
+
+        Ok(ServerInfo {
+            name: name.to_string(),
+            map: None,
+            version: version.map(str::to_string),
+            players: online.parse().unwrap_or_default(),
+            max_players: max.parse().unwrap_or_default(),
+            player_names: Vec::new(),
+        })
+    }
+}
+
+impl Query for MinecraftClient {
+    fn query(&self) -> Result<ServerInfo> {
+        match self.status() {
+            Ok(info) => Ok(info),
+            Err(error) => {
+                debug!("Status request failed, falling back to legacy ping: {error:?}");
+                self.legacy_ping()
+            }
+        }
+    }
+}
+
+/// Flatten a chat component (or plain string) into its text.
+fn chat_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter().map(chat_to_text).collect(),
+        Value::Object(component) => {
+            let mut text = component
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if let Some(extra) = component.get("extra") {
+                text.push_str(&chat_to_text(extra));
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+fn send_packet(stream: &mut TcpStream, packet: &[u8]) -> Result<()> {
+    let mut data = Vec::with_capacity(packet.len() + 5);
+    write_varint(&mut data, packet.len() as i32);
+    data.extend_from_slice(packet);
+    stream.write_all(&data).wrap_err("Failed to send packet")?;
+
+    Ok(())
+}
+
+fn write_varint(buffer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            buffer.push(value as u8);
+            return;
+        }
+        buffer.push((value & 0x7F | 0x80) as u8);
+        value >>= 7;
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    write_varint(buffer, value.len() as i32);
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<i32> {
+    let mut value = 0;
+    for position in 0..5 {
+        let mut byte = [0];
+        reader
+            .read_exact(&mut byte)
+            .wrap_err("Failed to read VarInt")?;
+        value |= ((byte[0] & 0x7F) as i32) << (7 * position);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    bail!("VarInt is too long");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Accept a single connection and let `handle` answer it.
+    fn serve(
+        handle: impl FnOnce(&mut TcpStream) -> Result<()> + Send + 'static,
+    ) -> (u16, thread::JoinHandle<Result<()>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept()?;
+            handle(&mut stream)
+        });
+
+        (port, handle)
+    }
+
+    fn read_packet(stream: &mut TcpStream) -> Result<Vec<u8>> {
+        let length = read_varint(stream)? as usize;
+        let mut packet = vec![0; length];
+        stream.read_exact(&mut packet)?;
+        Ok(packet)
+    }
+
+    #[test]
+    fn varints() -> Result<()> {
+        for (value, encoded) in [
+            (0, vec![0x00]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x01]),
+            (25565, vec![0xdd, 0xc7, 0x01]),
+            (-1, vec![0xff, 0xff, 0xff, 0xff, 0x0f]),
+        ] {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, value);
+            assert_eq!(buffer, encoded);
+            assert_eq!(read_varint(&mut buffer.as_slice())?, value);
+        }
+
+        assert!(read_varint(&mut [0xff; 6].as_slice()).is_err());
+        assert!(read_varint(&mut [0x80].as_slice()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn chat_components() {
+        assert_eq!(
+            chat_to_text(&json!("A Minecraft Server")),
+            "A Minecraft Server"
+        );
+        assert_eq!(
+            chat_to_text(&json!({
+                "text": "Survival ",
+                "extra": [{"text": "Server", "bold": true}, " 1.21"]
+            })),
+            "Survival Server 1.21"
+        );
+        assert_eq!(chat_to_text(&json!(null)), "");
+    }
+
+    #[test]
+    fn status() -> Result<()> {
+        let (port, server) = serve(|stream| {
+            let handshake = read_packet(stream)?;
+            // Packet id, protocol version -1 and the host
+            assert_eq!(&handshake[..6], &[0x00, 0xff, 0xff, 0xff, 0xff, 0x0f]);
+            assert_eq!(handshake.last(), Some(&1));
+            assert_eq!(read_packet(stream)?, vec![0x00]);
+
+            let response = json!({
+                "version": {"name": "1.21.4", "protocol": 769},
+                "players": {"max": 20, "online": 2, "sample": [{"name": "Alice", "id": "0"}]},
+                "description": {"text": "Survival"}
+            })
+            .to_string();
+            let mut packet = vec![0x00];
+            write_string(&mut packet, &response);
+            let mut data = Vec::new();
+            write_varint(&mut data, packet.len() as i32);
+            data.extend_from_slice(&packet);
+            stream.write_all(&data)?;
+
+            Ok(())
+        });
+
+        let info = MinecraftClient::new("127.0.0.1", port).status()?;
+        server.join().unwrap()?;
+
+        assert_eq!(info.name, "Survival");
+        assert_eq!(info.version.as_deref(), Some("1.21.4"));
+        assert_eq!((info.players, info.max_players), (2, 20));
+        assert_eq!(info.player_names, vec!["Alice"]);
+
+        Ok(())
+    }
+
+    fn legacy_response(response: &str) -> Vec<u8> {
+        let chars: Vec<u16> = response.encode_utf16().collect();
+        let mut data = vec![0xFF];
+        data.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+        for char in chars {
+            data.extend_from_slice(&char.to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn legacy_ping() -> Result<()> {
+        let (port, server) = serve(|stream| {
+            let mut request = [0; 2];
+            stream.read_exact(&mut request)?;
+            assert_eq!(request, [0xFE, 0x01]);
+            stream.write_all(&legacy_response(
+                "§1\x0047\x001.4.7\x00Old Server\x003\x0010",
+            ))?;
+            Ok(())
+        });
+
+        let info = MinecraftClient::new("127.0.0.1", port).legacy_ping()?;
+        server.join().unwrap()?;
+        assert_eq!(info.name, "Old Server");
+        assert_eq!(info.version.as_deref(), Some("1.4.7"));
+        assert_eq!((info.players, info.max_players), (3, 10));
+
+        // Servers older than 1.4 only send the MOTD and the player counts.
+        let (port, server) = serve(|stream| {
+            stream.read_exact(&mut [0; 2])?;
+            stream.write_all(&legacy_response("Ancient Server§0§8"))?;
+            Ok(())
+        });
+
+        let info = MinecraftClient::new("127.0.0.1", port).legacy_ping()?;
+        server.join().unwrap()?;
+        assert_eq!(info.name, "Ancient Server");
+        assert_eq!(info.version, None);
+        assert_eq!((info.players, info.max_players), (0, 8));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+
+use crate::errors::*;
+
+pub mod a2s;
+pub mod gamespy;
+pub mod minecraft;
+pub mod quake3;
+
+/// Information about a running server, gathered via one of the query protocols.
+#[derive(Clone, Debug, Default)]
+pub struct ServerInfo {
+    pub name: String,
+    pub map: Option<String>,
+    pub version: Option<String>,
+    pub players: u32,
+    pub max_players: u32,
+    /// The names of the online players.
+    /// Depending on the protocol, this may only be a sample of all players.
+    pub player_names: Vec<String>,
+}
+
+impl fmt::Display for ServerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Name: {}", self.name)?;
+        if let Some(map) = &self.map {
+            writeln!(f, "Map: {map}")?;
+        }
+        if let Some(version) = &self.version {
+            writeln!(f, "Version: {version}")?;
+        }
+        write!(f, "Players: {}/{}", self.players, self.max_players)?;
+        for name in &self.player_names {
+            write!(f, "\n  - {name}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Common interface of all query protocols.
+pub trait Query {
+    /// Ask the server for its current state.
+    fn query(&self) -> Result<ServerInfo>;
+}
+
+/// A small helper to read the little-endian binary responses of the query protocols.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data, position: 0 }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+
+    pub fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.remaining() < count {
+            bail!("Unexpected end of response");
+        }
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    pub fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    /// Read a null-terminated string.
+    pub fn string(&mut self) -> Result<String> {
+        let rest = &self.data[self.position..];
+        let end = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| eyre!("Unterminated string in response"))?;
+        let string = String::from_utf8_lossy(&rest[..end]).to_string();
+        self.position += end + 1;
+
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{net::UdpSocket, thread};
+
+    use super::*;
+
+    /// Answer the given number of UDP requests with the packets returned by `respond`.
+    ///
+    /// Returns the address of the server and a handle, which yields all received requests.
+    pub(crate) fn serve(
+        requests: usize,
+        respond: impl Fn(&[u8]) -> Vec<Vec<u8>> + Send + 'static,
+    ) -> (String, thread::JoinHandle<Vec<Vec<u8>>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            for _ in 0..requests {
+                let mut buffer = [0; 4096];
+                let (size, client) = socket.recv_from(&mut buffer).unwrap();
+                for packet in respond(&buffer[..size]) {
+                    socket.send_to(&packet, client).unwrap();
+                }
+                received.push(buffer[..size].to_vec());
+            }
+            received
+        });
+
+        (address, handle)
+    }
+
+    #[test]
+    fn reader() -> Result<()> {
+        let mut reader = Reader::new(b"\x01\x02\x00name\0rest");
+        assert_eq!(reader.u8()?, 1);
+        assert_eq!(reader.i16()?, 2);
+        assert_eq!(reader.string()?, "name");
+        assert_eq!(reader.remaining(), 4);
+        assert!(reader.string().is_err());
+        assert!(reader.i32().is_ok());
+        assert!(reader.u8().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn display_server_info() {
+        let info = ServerInfo {
+            name: "TTT Server".to_string(),
+            map: Some("ttt_minecraft_b5".to_string()),
+            version: None,
+            players: 2,
+            max_players: 16,
+            player_names: vec!["Alice".to_string(), "Bob".to_string()],
+        };
+
+        assert_eq!(
+            info.to_string(),
+            "Name: TTT Server\nMap: ttt_minecraft_b5\nPlayers: 2/16\n  - Alice\n  - Bob"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::{collections::BTreeMap, net::UdpSocket, time::Duration};
+
+use super::{Query, ServerInfo};
+use crate::errors::*;
+
+/// How long to wait for a response.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum size of a response packet.
+const MAX_PACKET_SIZE: usize = 16384;
+
+/// Out-of-band packets start with four `0xFF` bytes.
+const OUT_OF_BAND: [u8; 4] = [0xFF; 4];
+
+/// A single entry of the player list in a `getstatus` response.
+#[derive(Clone, Debug)]
+pub struct Quake3Player {
+    pub name: String,
+    pub score: i32,
+    pub ping: u32,
+}
+
+/// The response to a `getstatus` request.
+#[derive(Clone, Debug)]
+pub struct Quake3Status {
+    /// The server's public cvars, e.g. `sv_hostname` or `mapname`.
+    pub cvars: BTreeMap<String, String>,
+    pub players: Vec<Quake3Player>,
+}
+
+/// A client for the out-of-band query protocol of the Quake 3 engine.
+///
+/// The protocol is spoken by Quake 3 and its descendants, such as Call of Duty 4.
+pub struct Quake3Client {
+    /// The address of the server, e.g. `127.0.0.1:28960`.
+    address: String,
+}
+
+impl Quake3Client {
+    pub fn new(address: impl ToString) -> Self {
+        Quake3Client {
+            address: address.to_string(),
+        }
+    }
+
+    /// Query the server's public cvars and its players.
+    pub fn status(&self) -> Result<Quake3Status> {
+        let response = self.request("getstatus", "statusResponse")?;
+
+        let mut lines = response.lines();
+        let cvars = parse_info_string(lines.next().unwrap_or_default());
+
+        // Each player is a line in the format `{score} {ping} "{name}"`.
+        let mut players = Vec::new();
+        for line in lines.filter(|line| !line.is_empty()) {
+            let mut parts = line.splitn(3, ' ');
+            let (Some(score), Some(ping), Some(name)) = (parts.next(), parts.next(), parts.next())
+            else {
+                bail!("Got malformed player line in getstatus response: {line}");
+            };
+
+            players.push(Quake3Player {
+                name: strip_colors(name.trim_matches('"')),
+                score: score.parse().unwrap_or_default(),
+                ping: ping.parse().unwrap_or_default(),
+            });
+        }
+
+        Ok(Quake3Status { cvars, players })
+    }
+
+    /// Query the server's summary, which is what server browsers show.
+    pub fn info(&self) -> Result<BTreeMap<String, String>> {
+        let response = self.request("getinfo xxx", "infoResponse")?;
+        Ok(parse_info_string(
+            response.lines().next().unwrap_or_default(),
+        ))
+    }
+
+    /// Send an out-of-band request and return the response without its header line.
+    fn request(&self, command: &str, expected: &str) -> Result<String> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(TIMEOUT))?;
+        socket
+            .connect(&self.address)
+            .wrap_err(format!("Failed to connect to {}", self.address))?;
+
+        let mut packet = OUT_OF_BAND.to_vec();
+        packet.extend_from_slice(command.as_bytes());
+        packet.push(b'\n');
+        socket
+            .send(&packet)
+            .wrap_err(format!("Failed to send query to {}", self.address))?;
+
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        let size = socket.recv(&mut buffer).wrap_err(format!(
+            "Failed to receive query response from {}",
+            self.address
+        ))?;
+
+        let response = buffer[..size]
+            .strip_prefix(&OUT_OF_BAND)
+            .ok_or_else(|| eyre!("Got response without out-of-band header"))?;
+        let response = String::from_utf8_lossy(response);
+        let (header, body) = response.split_once('\n').unwrap_or((&response, ""));
+        if header.trim() != expected {
+            bail!("Got unexpected response to {command}: {header}");
+        }
+
+        Ok(body.to_string())
+    }
+}
+
+impl Query for Quake3Client {
+    fn query(&self) -> Result<ServerInfo> {
+        let status = self.status()?;
+        let cvar = |name: &str| status.cvars.get(name).filter(|value| !value.is_empty());
+
+        Ok(ServerInfo {
+            name: cvar("sv_hostname")
+                .map(|name| strip_colors(name))
+                .unwrap_or_default(),
+            map: cvar("mapname").cloned(),
+            version: cvar("shortversion").or_else(|| cvar("version")).cloned(),
+            players: status.players.len() as u32,
+            max_players: cvar("sv_maxclients")
+                .and_then(|max| max.parse().ok())
+                .unwrap_or_default(),
+            player_names: status
+                .players
+                .into_iter()
+                .map(|player| player.name)
+                .collect(),
+        })
+    }
+}
+
+/// Parse an info string in the format `\key\value\key\value`.
+fn parse_info_string(info: &str) -> BTreeMap<String, String> {
+    let mut parts = info.strip_prefix('\\').unwrap_or(info).split('\\');
+    let mut values = BTreeMap::new();
+    while let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+        values.insert(key.to_string(), value.to_string());
+    }
+
+    values
+}
+
+/// Remove color codes such as `^2` from a name.
+fn strip_colors(name: &str) -> String {
+    let mut stripped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(char) = chars.next() {
+        if char == '^' {
+            chars.next();
+            continue;
+        }
+        stripped.push(char);
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::tests::serve;
+
+    #[test]
+    fn info_strings_and_colors() {
+        let values = parse_info_string("\\sv_hostname\\^2nuke's ^7server\\mapname\\mp_crash");
+        assert_eq!(values["sv_hostname"], "^2nuke's ^7server");
+        assert_eq!(values["mapname"], "mp_crash");
+
+        assert_eq!(
+            strip_colors("^2nuke's ^7cod4 ^1hc server"),
+            "nuke's cod4 hc server"
+        );
+        assert_eq!(strip_colors("trailing^"), "trailing");
+    }
+
+    #[test]
+    fn status() -> Result<()> {
+        let (address, server) = serve(1, |request| {
+            assert_eq!(request, b"\xff\xff\xff\xffgetstatus\n");
+            vec![
+                b"\xff\xff\xff\xffstatusResponse\n\\sv_hostname\\^2nuke's ^7server\\mapname\\mp_crash\\shortversion\\1.7\\sv_maxclients\\16\n12 50 \"^1Alice\"\n-3 999 \"Bob Builder\"\n"
+                    .to_vec(),
+            ]
+        });
+
+        let info = Quake3Client::new(address).query()?;
+        server.join().unwrap();
+
+        assert_eq!(info.name, "nuke's server");
+        assert_eq!(info.map.as_deref(), Some("mp_crash"));
+        assert_eq!(info.version.as_deref(), Some("1.7"));
+        assert_eq!((info.players, info.max_players), (2, 16));
+        assert_eq!(info.player_names, vec!["Alice", "Bob Builder"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_players() -> Result<()> {
+        let (address, server) = serve(1, |_| {
+            vec![b"\xff\xff\xff\xffstatusResponse\n\\mapname\\mp_vacant\n5 48 \"Carl\"\n".to_vec()]
+        });
+
+        let status = Quake3Client::new(address).status()?;
+        server.join().unwrap();
+
+        assert_eq!(status.players.len(), 1);
+        assert_eq!(status.players[0].name, "Carl");
+        assert_eq!(status.players[0].score, 5);
+        assert_eq!(status.players[0].ping, 48);
+
+        Ok(())
+    }
+
+    #[test]
+    fn info() -> Result<()> {
+        let (address, server) = serve(1, |request| {
+            assert_eq!(request, b"\xff\xff\xff\xffgetinfo xxx\n");
+            vec![b"\xff\xff\xff\xffinfoResponse\n\\hostname\\server\\clients\\0".to_vec()]
+        });
+
+        let info = Quake3Client::new(address).info()?;
+        server.join().unwrap();
+        assert_eq!(info["clients"], "0");
+
+        Ok(())
+    }
+
+    #[test]
+    fn unexpected_responses() {
+        let (address, server) = serve(1, |_| vec![b"statusResponse\n".to_vec()]);
+        assert!(Quake3Client::new(address).status().is_err());
+        server.join().unwrap();
+
+        let (address, server) = serve(1, |_| vec![b"\xff\xff\xff\xffinfoResponse\n".to_vec()]);
+        assert!(Quake3Client::new(address).status().is_err());
+        server.join().unwrap();
+    }
+}
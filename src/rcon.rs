@@ -0,0 +1,319 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::errors::*;
+
+/// How long to wait for a connection or a response, unless a command needs longer.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximum size of a packet's body that's accepted from the server.
+///
+/// Source servers split their responses into packets of 4096 bytes, but Minecraft and Factorio
+/// send large responses in a single packet.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+// Packet types of the Source RCON protocol.
+// Note that `SERVERDATA_EXECCOMMAND` and `SERVERDATA_AUTH_RESPONSE` share the same value.
+const SERVERDATA_AUTH: i32 = 3;
+const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+const SERVERDATA_EXECCOMMAND: i32 = 2;
+const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// The settings that're needed to connect to a server via RCON.
+#[derive(Clone, Debug)]
+pub struct RconSettings {
+    /// The address of the server, e.g. `127.0.0.1:27015`.
+    pub address: String,
+    pub password: String,
+}
+
+#[derive(Debug)]
+struct Packet {
+    id: i32,
+    kind: i32,
+    body: Vec<u8>,
+}
+
+/// A client for the Source RCON protocol.
+///
+/// The protocol is spoken by all Source engine games, but also by Minecraft and Factorio.
+/// See https://developer.valvesoftware.com/wiki/Source_RCON_Protocol
+pub struct RconClient {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl RconClient {
+    /// Connect to a server and authenticate with the given password.
+    pub fn connect(settings: &RconSettings) -> Result<Self> {
+        let address = settings
+            .address
+            .to_socket_addrs()
+            .wrap_err(format!("Failed to resolve {}", settings.address))?
+            .next()
+            .ok_or_else(|| eyre!("Couldn't resolve {}", settings.address))?;
+
+        let stream = TcpStream::connect_timeout(&address, TIMEOUT)
+            .wrap_err(format!("Failed to connect to RCON at {address}"))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut client = RconClient { stream, next_id: 1 };
+        client.authenticate(&settings.password)?;
+
+        Ok(client)
+    }
+
+    fn authenticate(&mut self, password: &str) -> Result<()> {
+        let id = self.send(SERVERDATA_AUTH, password)?;
+
+        // Source servers send an empty response value before the actual auth response.
+        loop {
+            let packet = self.receive()?;
+            if packet.kind != SERVERDATA_AUTH_RESPONSE {
+                continue;
+            }
+
+            if packet.id == -1 {
+                bail!("RCON authentication failed, the password is wrong");
+            }
+            if packet.id != id {
+                bail!("Got RCON auth response for unexpected id {}", packet.id);
+            }
+
+            return Ok(());
+        }
+    }
+
+    /// Execute a command and return its output.
+    ///
+    /// Large responses are split into multiple packets by the server.
+    /// To know when the response is complete, an empty packet is sent right after the command.
+    /// The server answers it only after all packets of the actual response have been sent.
+    pub fn command(&mut self, command: &str) -> Result<String> {
+        self.command_with_timeout(command, TIMEOUT)
+    }
+
+    /// Execute a command, whose response might take longer than the default [TIMEOUT],
+    /// e.g. because the server only answers once it saved the world.
+    pub fn command_with_timeout(&mut self, command: &str, timeout: Duration) -> Result<String> {
+        self.stream.set_read_timeout(Some(timeout))?;
+        let response = self.read_command(command);
+        self.stream.set_read_timeout(Some(TIMEOUT))?;
+
+        response
+    }
+
+    fn read_command(&mut self, command: &str) -> Result<String> {
+        let id = self.send(SERVERDATA_EXECCOMMAND, command)?;
+        let sentinel_id = self.send(SERVERDATA_RESPONSE_VALUE, "")?;
+
+        // Multi-byte characters might be split between packets, so the body is only decoded once
+        // the whole response has been received.
+        let mut response = Vec::new();
+        let mut received = false;
+        loop {
+            let packet = match self.read_packet() {
+                Ok(packet) => packet,
+                // Not all servers answer the empty packet.
+                // In that case, the response is considered complete once the server stops sending.
+                Err(error)
+                    if received
+                        && matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    break;
+                }
+                Err(error) => return Err(error).wrap_err("Failed to read RCON response"),
+            };
+            if packet.id == sentinel_id {
+                break;
+            }
+            if packet.id != id {
+                debug!("Ignoring RCON packet with unexpected id {}", packet.id);
+                continue;
+            }
+
+            received = true;
+            response.extend_from_slice(&packet.body);
+        }
+
+        Ok(String::from_utf8_lossy(&response).to_string())
+    }
+
+    fn send(&mut self, kind: i32, body: &str) -> Result<i32> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.stream
+            .write_all(&encode_packet(id, kind, body))
+            .wrap_err("Failed to send RCON packet")?;
+
+        Ok(id)
+    }
+
+    fn receive(&mut self) -> Result<Packet> {
+        self.read_packet().wrap_err("Failed to read RCON packet")
+    }
+
+    fn read_packet(&mut self) -> io::Result<Packet> {
+        read_packet(&mut self.stream)
+    }
+}
+
+fn encode_packet(id: i32, kind: i32, body: &str) -> Vec<u8> {
+    // The size covers the id, the type, the body and two null bytes.
+    let size = (body.len() + 10) as i32;
+    let mut packet = Vec::with_capacity(body.len() + 14);
+    packet.extend_from_slice(&size.to_le_bytes());
+    packet.extend_from_slice(&id.to_le_bytes());
+    packet.extend_from_slice(&kind.to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet.extend_from_slice(&[0, 0]);
+
+    packet
+}
+
+fn read_packet(reader: &mut impl Read) -> io::Result<Packet> {
+    let mut size = [0; 4];
+    reader.read_exact(&mut size)?;
+    let size = i32::from_le_bytes(size);
+    if size < 10 || size as usize > MAX_BODY_SIZE + 10 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Got RCON packet with invalid size {size}"),
+        ));
+    }
+
+    let mut data = vec![0; size as usize];
+    reader.read_exact(&mut data)?;
+
+    let id = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    let kind = i32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    // Strip the two trailing null bytes.
+    data.truncate(data.len() - 2);
+    let body = data.split_off(8);
+
+    Ok(Packet { id, kind, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, net::TcpListener, thread};
+
+    use super::*;
+
+    /// Encode a packet with a raw body, which might not be valid UTF-8 on its own.
+    fn raw_packet(id: i32, kind: i32, body: &[u8]) -> Vec<u8> {
+        let mut packet = ((body.len() + 10) as i32).to_le_bytes().to_vec();
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&kind.to_le_bytes());
+        packet.extend_from_slice(body);
+        packet.extend_from_slice(&[0, 0]);
+        packet
+    }
+
+    #[test]
+    fn encode_and_read_packet() -> Result<()> {
+        let packet = encode_packet(7, SERVERDATA_EXECCOMMAND, "status");
+        assert_eq!(packet.len(), 4 + 4 + 4 + 6 + 2);
+        assert_eq!(&packet[..4], &16i32.to_le_bytes());
+        assert_eq!(&packet[packet.len() - 2..], &[0, 0]);
+
+        let packet = read_packet(&mut Cursor::new(packet))?;
+        assert_eq!(packet.id, 7);
+        assert_eq!(packet.kind, SERVERDATA_EXECCOMMAND);
+        assert_eq!(packet.body, b"status");
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_empty_and_large_packets() -> Result<()> {
+        let packet = read_packet(&mut Cursor::new(encode_packet(1, 0, "")))?;
+        assert!(packet.body.is_empty());
+
+        // Minecraft sends responses that're larger than 4096 bytes in a single packet.
+        let body = "a".repeat(100_000);
+        let packet = read_packet(&mut Cursor::new(encode_packet(1, 0, &body)))?;
+        assert_eq!(packet.body.len(), body.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_sizes() {
+        for size in [-1i32, 0, 9, (MAX_BODY_SIZE + 11) as i32] {
+            let mut data = size.to_le_bytes().to_vec();
+            data.extend_from_slice(&[0; 16]);
+            let error = read_packet(&mut Cursor::new(data)).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+        }
+
+        // The packet is shorter than its size claims.
+        let packet = encode_packet(1, 0, "status");
+        let error = read_packet(&mut Cursor::new(&packet[..packet.len() - 1])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn command_with_multi_packet_response() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+
+        let server = thread::spawn(move || -> io::Result<()> {
+            let (mut stream, _) = listener.accept()?;
+
+            // Authentication, preceded by an empty response value like Source servers do it.
+            let auth = read_packet(&mut stream)?;
+            assert_eq!(auth.kind, SERVERDATA_AUTH);
+            assert_eq!(auth.body, b"hunter2");
+            stream.write_all(&raw_packet(auth.id, SERVERDATA_RESPONSE_VALUE, b""))?;
+            stream.write_all(&raw_packet(auth.id, SERVERDATA_AUTH_RESPONSE, b""))?;
+
+            let command = read_packet(&mut stream)?;
+            assert_eq!(command.body, b"status");
+            let sentinel = read_packet(&mut stream)?;
+
+            // The response is split in the middle of the multi-byte `ü`.
+            let response = "Grüße".as_bytes();
+            stream.write_all(&raw_packet(command.id, 0, &response[..3]))?;
+            stream.write_all(&raw_packet(command.id, 0, &response[3..]))?;
+            stream.write_all(&raw_packet(sentinel.id, 0, b""))?;
+
+            Ok(())
+        });
+
+        let mut client = RconClient::connect(&RconSettings {
+            address,
+            password: "hunter2".to_string(),
+        })?;
+        assert_eq!(client.command("status")?, "Grüße");
+        server.join().unwrap()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_password() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+
+        let server = thread::spawn(move || -> io::Result<()> {
+            let (mut stream, _) = listener.accept()?;
+            read_packet(&mut stream)?;
+            stream.write_all(&raw_packet(-1, SERVERDATA_AUTH_RESPONSE, b""))
+        });
+
+        let result = RconClient::connect(&RconSettings {
+            address,
+            password: "wrong".to_string(),
+        });
+        assert!(result.is_err());
+        server.join().unwrap()?;
+
+        Ok(())
+    }
+}
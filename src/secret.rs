@@ -18,6 +18,20 @@ pub fn copy_secret_file(src: &Path, dest: &Path, secrets: &HashMap<&str, String>
         remove_file(dest).wrap_err("Failed deleting old config file")?;
     }
 
+    let content = render_secret_file(src, secrets)?;
+
+    File::create(dest)
+        .wrap_err("Failed to create destination config file")?
+        .write_all(content.as_bytes())
+        .wrap_err("Failed to write to destination config file")?;
+
+    Ok(())
+}
+
+/// Read a secret file and replace all placeholders with the given secrets.
+///
+/// This is the same as [copy_secret_file], but returns the content instead of writing it.
+pub fn render_secret_file(src: &Path, secrets: &HashMap<&str, String>) -> Result<String> {
     let mut src = File::open(src).wrap_err("Failed to open source config file")?;
     let mut content = String::new();
     src.read_to_string(&mut content)
@@ -27,10 +41,5 @@ pub fn copy_secret_file(src: &Path, dest: &Path, secrets: &HashMap<&str, String>
         content = content.replace(&format!("{{{{ {key} }}}}"), value);
     }
 
-     File::create(dest)
-        .wrap_err("Failed to create destination config file")?
-        .write_all(content.as_bytes())
-        .wrap_err("Failed to write to destination config file")?;
-
-    Ok(())
+    Ok(content)
 }
@@ -0,0 +1,116 @@
+use std::{
+    fs::{create_dir_all, remove_dir_all, remove_file, rename},
+    path::Path,
+};
+
+use crate::{cmd, errors::*, process::*};
+
+/// Remove a file or directory, if it exists.
+fn remove_path(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        remove_dir_all(path).wrap_err(format!("Failed to remove directory {path:?}"))?;
+    } else if path.exists() || path.is_symlink() {
+        remove_file(path).wrap_err(format!("Failed to remove file {path:?}"))?;
+    }
+
+    Ok(())
+}
+
+/// Copy a whole directory, including permissions and symlinks.
+///
+/// Reflinks are used if the filesystem supports them, which makes snapshots of large game
+/// installations basically free.
+fn copy_directory(src: &Path, dest: &Path) -> Result<()> {
+    cmd!(
+        "cp -a --reflink=auto {} {}",
+        src.to_string_lossy(),
+        dest.to_string_lossy()
+    )
+    .run_success()
+    .wrap_err(format!("Failed to copy {src:?} to {dest:?}"))?;
+
+    Ok(())
+}
+
+/// Take a snapshot of a game installation.
+///
+/// Any previous snapshot at that location is replaced.
+/// The `preserved` paths (relative to the game dir) contain user data such as saves and configs.
+/// They aren't part of the snapshot, as they're never touched by a rollback.
+///
+/// Returns whether a snapshot has been taken, which isn't the case for fresh installations.
+pub fn create_snapshot(game_dir: &Path, snapshot_dir: &Path, preserved: &[&str]) -> Result<bool> {
+    // A snapshot of a previous installation must never be restored over a new one.
+    remove_path(snapshot_dir)?;
+    if !game_dir.exists() {
+        info!("{game_dir:?} doesn't exist yet, nothing to snapshot");
+        return Ok(false);
+    }
+
+    if let Some(parent) = snapshot_dir.parent() {
+        create_dir_all(parent).wrap_err(format!("Failed to create snapshot dir: {parent:?}"))?;
+    }
+
+    info!("Creating snapshot of {game_dir:?} at {snapshot_dir:?}");
+    copy_directory(game_dir, snapshot_dir)?;
+
+    for path in preserved {
+        remove_path(&snapshot_dir.join(path))?;
+    }
+
+    Ok(true)
+}
+
+/// Restore a game installation from a snapshot.
+///
+/// The `preserved` paths are taken from the current installation, so saves and configs that were
+/// changed since the snapshot has been taken aren't lost.
+/// The snapshot itself is kept, so a rollback can be repeated.
+pub fn restore_snapshot(snapshot_dir: &Path, game_dir: &Path, preserved: &[&str]) -> Result<()> {
+    if !snapshot_dir.exists() {
+        bail!("There's no snapshot at {snapshot_dir:?} to roll back to");
+    }
+
+    // Move the preserved files next to the game dir while the installation is replaced.
+    // That way, they stay on the same filesystem and can simply be renamed.
+    let mut preserved_name = game_dir.file_name().unwrap_or_default().to_os_string();
+    preserved_name.push(".preserved");
+    let preserved_dir = game_dir.with_file_name(preserved_name);
+    remove_path(&preserved_dir)?;
+    create_dir_all(&preserved_dir)
+        .wrap_err(format!("Failed to create directory {preserved_dir:?}"))?;
+    if let Err(error) = move_paths(game_dir, &preserved_dir, preserved) {
+        move_paths(&preserved_dir, game_dir, preserved)?;
+        return Err(error);
+    }
+
+    info!("Restoring {game_dir:?} from {snapshot_dir:?}");
+    let result = remove_path(game_dir).and_then(|_| copy_directory(snapshot_dir, game_dir));
+
+    // Move the preserved files back in place, even if the restore failed.
+    move_paths(&preserved_dir, game_dir, preserved)?;
+    result?;
+    remove_path(&preserved_dir)?;
+
+    Ok(())
+}
+
+/// Move the given relative paths from one directory to another, if they exist.
+///
+/// Existing files at the destination are replaced.
+pub fn move_paths(from: &Path, to: &Path, paths: &[&str]) -> Result<()> {
+    for path in paths {
+        let src = from.join(path);
+        if src.exists() {
+            let dest = to.join(path);
+            if let Some(parent) = dest.parent() {
+                create_dir_all(parent)?;
+            }
+            remove_path(&dest)?;
+            info!("Moving {src:?} to {dest:?}");
+            rename(&src, &dest).wrap_err(format!("Failed to move {src:?} to {dest:?}"))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,402 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    fs::{File, create_dir_all, read_dir, remove_dir_all, write},
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cmd,
+    errors::*,
+    process::*,
+    steam::SteamServer,
+    tmux::TmuxServer,
+    workshop::{self, WorkshopItem, WorkshopReport},
+};
+
+/// Where a map has been found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapLocation {
+    /// The map is part of the installation, i.e. it's in the `maps` folder.
+    Installed,
+    /// The map has been downloaded from the workshop.
+    Workshop,
+    Missing,
+}
+
+impl fmt::Display for MapLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapLocation::Installed => write!(f, "installed"),
+            MapLocation::Workshop => write!(f, "workshop"),
+            MapLocation::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+/// Make sure a map name can be safely sent to the server console and used as a path.
+///
+/// Workshop maps contain slashes, e.g. `workshop/3070284539` for CS2.
+fn ensure_valid_map_name(map: &str) -> Result<()> {
+    let valid_chars = map
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.' | '/'));
+    if map.is_empty() || !valid_chars || map.contains("..") {
+        bail!("Invalid map name: {map}");
+    }
+
+    Ok(())
+}
+
+/// Get the id of a workshop map that's referenced by its id, e.g. `workshop/3070284539`.
+fn workshop_map_id(map: &str) -> Option<&str> {
+    map.strip_prefix("workshop/")
+        .filter(|id| !id.is_empty() && id.chars().all(|char| char.is_ascii_digit()))
+}
+
+/// Get all files in a directory and its subdirectories.
+/// Directories that don't exist don't contain any files.
+fn files_recursive(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    for entry in read_dir(dir).wrap_err(format!("Failed to read {dir:?}"))? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            files.extend(files_recursive(&entry.path())?);
+        } else {
+            files.push(entry.path());
+        }
+    }
+
+    Ok(files)
+}
+
+/// Read a null-terminated string of a `.gma` header.
+fn read_gma_string(reader: &mut impl BufRead) -> Result<String> {
+    let mut bytes = Vec::new();
+    reader.read_until(0, &mut bytes)?;
+    if bytes.pop() != Some(0) {
+        bail!("Unexpected end of addon header");
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Read the paths of all files that're packed into a Garry's mod addon (`.gma`).
+///
+/// Only the header is read, which contains the uncompressed file table.
+fn read_gma_files(reader: &mut impl BufRead) -> Result<Vec<String>> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"GMAD" {
+        bail!("Not an addon file");
+    }
+    let mut version = [0; 1];
+    reader.read_exact(&mut version)?;
+
+    // Skip the steam id and the timestamp.
+    let mut skipped = [0; 16];
+    reader.read_exact(&mut skipped)?;
+    // Newer versions have a list of required content, which is terminated by an empty string.
+    if version[0] > 1 {
+        while !read_gma_string(reader)?.is_empty() {}
+    }
+    // Name, description and author, followed by the addon version.
+    for _ in 0..3 {
+        read_gma_string(reader)?;
+    }
+    let mut addon_version = [0; 4];
+    reader.read_exact(&mut addon_version)?;
+
+    // Each entry consists of its number, the path, the size (u64) and the crc (u32).
+    // The list is terminated by the number 0.
+    let mut files = Vec::new();
+    loop {
+        let mut number = [0; 4];
+        reader.read_exact(&mut number)?;
+        if u32::from_le_bytes(number) == 0 {
+            break;
+        }
+        files.push(read_gma_string(reader)?);
+        let mut size_and_crc = [0; 12];
+        reader.read_exact(&mut size_and_crc)?;
+    }
+
+    Ok(files)
+}
+
+/// The maps that've been found in the workshop directories.
+#[derive(Debug, Default)]
+struct WorkshopMaps {
+    /// The names of all plain map files, e.g. `de_dust2_se.bsp`.
+    file_names: BTreeSet<String>,
+    /// The ids of the workshop items that contain a plain map file.
+    /// Items are downloaded into directories that're named after their id.
+    item_ids: BTreeSet<String>,
+    /// The paths of all files that're packed into addons, e.g. `maps/ttt_rooftops.bsp`.
+    addon_files: BTreeSet<String>,
+}
+
+/// Collect the maps of all workshop directories.
+///
+/// Workshop maps are either plain map files or packed into `.gma` addons.
+fn workshop_maps(dirs: &[PathBuf], extension: &str) -> Result<WorkshopMaps> {
+    let mut maps = WorkshopMaps::default();
+    for dir in dirs {
+        for file in files_recursive(dir)? {
+            let file_name = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            if file_name.ends_with(".gma") {
+                let file_handle = File::open(&file).wrap_err(format!("Failed to open {file:?}"))?;
+                match read_gma_files(&mut BufReader::new(file_handle)) {
+                    Ok(files) => maps.addon_files.extend(files),
+                    Err(error) => warn!("Failed to read addon {file:?}: {error}"),
+                }
+            } else if file.extension().is_some_and(|ext| ext == extension) {
+                if let Some(item_id) = file
+                    .parent()
+                    .and_then(|parent| parent.file_name())
+                    .map(|name| name.to_string_lossy().to_string())
+                {
+                    maps.item_ids.insert(item_id);
+                }
+                maps.file_names.insert(file_name);
+            }
+        }
+    }
+
+    Ok(maps)
+}
+
+/// Source engine servers, such as CS2 or Garry's mod.
+pub trait SourceServer: SteamServer + TmuxServer {
+    /// The folder of the game inside the game dir, e.g. `game/csgo` or `garrysmod`.
+    fn game_folder(&self) -> &'static str;
+
+    /// The app id of the game that owns the workshop items.
+    /// This is the id of the game itself, not the one of the dedicated server.
+    fn workshop_app_id(&self) -> u32;
+
+    /// The file extension of maps, e.g. `bsp` or `vpk` for CS2.
+    fn map_extension(&self) -> &'static str {
+        "bsp"
+    }
+
+    fn game_folder_path(&self) -> PathBuf {
+        self.config().game_dir().join(self.game_folder())
+    }
+
+    /// The directory that workshop items are downloaded to via steamcmd.
+    /// Each item gets its own directory that's named after the item's id.
+    fn workshop_content_dir(&self) -> PathBuf {
+        self.config()
+            .game_dir()
+            .join("steamapps/workshop/content")
+            .join(self.workshop_app_id().to_string())
+    }
+
+    /// All directories that might contain maps from the workshop.
+    ///
+    /// CS2 puts them into `maps/workshop`, Garry's mod keeps its addons in `cache/srcds`.
+    /// Items that're downloaded via steamcmd end up in `steamapps/workshop`.
+    fn workshop_dirs(&self) -> Vec<PathBuf> {
+        vec![
+            self.game_folder_path().join("maps/workshop"),
+            self.game_folder_path().join("cache/srcds"),
+            self.workshop_content_dir(),
+        ]
+    }
+
+    /// Find out whether maps are installed or have been downloaded from the workshop.
+    ///
+    /// Maps can either be referenced by their name or, if the game supports it, by the id of their
+    /// workshop item, e.g. `workshop/3070284539`.
+    /// The workshop directories are only scanned once for all maps.
+    fn locate_maps(&self, maps: &[String]) -> Result<Vec<MapLocation>> {
+        // The workshop directories are only scanned once a map isn't installed.
+        let mut found = None;
+        let mut locations = Vec::new();
+        for map in maps {
+            ensure_valid_map_name(map)?;
+
+            let extension = self.map_extension();
+            let map_path = self
+                .game_folder_path()
+                .join(format!("maps/{map}.{extension}"));
+            if map_path.exists() {
+                if map.starts_with("workshop/") {
+                    locations.push(MapLocation::Workshop);
+                } else {
+                    locations.push(MapLocation::Installed);
+                }
+                continue;
+            }
+
+            let found = match &found {
+                Some(found) => found,
+                None => found.insert(workshop_maps(&self.workshop_dirs(), extension)?),
+            };
+            let file_name = format!("{}.{extension}", map.rsplit('/').next().unwrap_or(map));
+            let is_workshop_item =
+                workshop_map_id(map).is_some_and(|id| found.item_ids.contains(id));
+            if is_workshop_item
+                || found.file_names.contains(&file_name)
+                || found
+                    .addon_files
+                    .contains(&format!("maps/{map}.{extension}"))
+            {
+                locations.push(MapLocation::Workshop);
+            } else {
+                locations.push(MapLocation::Missing);
+            }
+        }
+
+        Ok(locations)
+    }
+
+    /// Find out whether a map is installed or has been downloaded from the workshop.
+    fn locate_map(&self, map: &str) -> Result<MapLocation> {
+        let mut locations = self.locate_maps(&[map.to_string()])?;
+        Ok(locations.pop().unwrap_or(MapLocation::Missing))
+    }
+
+    /// Print the maps and where they've been found.
+    fn list_maps(&self, maps: &[String]) -> Result<()> {
+        for (map, location) in maps.iter().zip(self.locate_maps(maps)?) {
+            println!("{map}: {location}");
+        }
+
+        Ok(())
+    }
+
+    /// Write the maps to the `mapcycle.txt` and `maplist.txt` of the server.
+    ///
+    /// Fails if any map can't be found, unless `force` is set.
+    /// Maps from workshop collections are only downloaded once the server starts, so they might
+    /// not be available yet.
+    fn write_map_cycle(&self, maps: &[String], force: bool) -> Result<()> {
+        let mut missing = Vec::new();
+        for (map, location) in maps.iter().zip(self.locate_maps(maps)?) {
+            if location == MapLocation::Missing {
+                missing.push(map.as_str());
+            }
+        }
+        if !missing.is_empty() {
+            let message = format!("Couldn't find maps: {}", missing.join(", "));
+            if !force {
+                bail!(message);
+            }
+            warn!("{} - {message}", self.session_name());
+        }
+
+        let mut content = maps.join("\n");
+        content.push('\n');
+        for file in ["mapcycle.txt", "maplist.txt"] {
+            let path = self.game_folder_path().join(file);
+            write(&path, &content).wrap_err(format!("Failed to write {path:?}"))?;
+        }
+        info!(
+            "{} - Wrote map cycle with {} maps",
+            self.session_name(),
+            maps.len()
+        );
+
+        Ok(())
+    }
+
+    /// Change the map of the running server.
+    fn change_level(&self, map: &str) -> Result<()> {
+        if self.locate_map(map)? == MapLocation::Missing {
+            bail!("Couldn't find map {map}");
+        }
+        self.ensure_session_is_open()?;
+
+        // Maps that're referenced by their workshop id are loaded via `host_workshop_map`.
+        let command = match workshop_map_id(map) {
+            Some(id) => format!("host_workshop_map {id}"),
+            None => format!("changelevel {map}"),
+        };
+        if let Some(output) = self.send_command(&command)? {
+            println!("{output}");
+        }
+
+        Ok(())
+    }
+
+    /// Download all items of a workshop collection via steamcmd.
+    ///
+    /// Only items that changed since the last sync (or whose content is missing) are downloaded.
+    /// Content of items that have been removed from the collection is deleted.
+    fn sync_workshop(&self, collection: u64) -> Result<(Vec<WorkshopItem>, WorkshopReport)> {
+        let items = workshop::collection_items(&self.config().steam_api_url, collection)?;
+
+        // The items of the last sync are stored next to the downloaded content.
+        let content_dir = self.workshop_content_dir();
+        let state_path = self
+            .config()
+            .game_dir()
+            .join(format!("steamapps/workshop/collection_{collection}.json"));
+        let previous: Vec<WorkshopItem> = if state_path.exists() {
+            let file =
+                File::open(&state_path).wrap_err(format!("Failed to open {state_path:?}"))?;
+            serde_json::from_reader(file).wrap_err(format!("Failed to parse {state_path:?}"))?
+        } else {
+            Vec::new()
+        };
+        let report = WorkshopReport::new(&previous, &items);
+
+        let downloads: Vec<&WorkshopItem> = items
+            .iter()
+            .filter(|item| report.is_changed(item) || !content_dir.join(&item.id).exists())
+            .collect();
+        if !downloads.is_empty() {
+            info!(
+                "{} - Downloading {} workshop items",
+                self.session_name(),
+                downloads.len()
+            );
+            let download_args: Vec<String> = downloads
+                .iter()
+                .map(|item| {
+                    format!(
+                        "+workshop_download_item {} {}",
+                        self.workshop_app_id(),
+                        item.id
+                    )
+                })
+                .collect();
+            cmd!(
+                "steamcmd +force_install_dir {} +login anonymous {} +quit",
+                self.config().game_dir_str(),
+                download_args.join(" ")
+            )
+            .io_passthrough()
+            .run_success()
+            .wrap_err("Failed to download workshop items via steamcmd")?;
+        }
+
+        for item in &report.removed {
+            let item_dir = content_dir.join(&item.id);
+            if item_dir.exists() {
+                info!("Removing content of workshop item {item}");
+                remove_dir_all(&item_dir).wrap_err(format!("Failed to remove {item_dir:?}"))?;
+            }
+        }
+
+        if let Some(parent) = state_path.parent() {
+            create_dir_all(parent)?;
+        }
+        let file =
+            File::create(&state_path).wrap_err(format!("Failed to create {state_path:?}"))?;
+        serde_json::to_writer_pretty(file, &items)
+            .wrap_err(format!("Failed to write {state_path:?}"))?;
+
+        Ok((items, report))
+    }
+}
@@ -0,0 +1,481 @@
+use std::{fs::read_to_string, path::PathBuf};
+
+use crate::{cmd, errors::*, prelude::GameServer, process::*};
+
+/// A node of Valve's KeyValues format (used by `.acf` and `.vdf` files).
+///
+/// The format is a nested list of quoted key/value pairs, where a value can either be a string
+/// or a `{ ... }` block of further key/value pairs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyValues {
+    Value(String),
+    Section(Vec<(String, KeyValues)>),
+}
+
+impl KeyValues {
+    /// Parse a KeyValues document.
+    ///
+    /// The document is returned as a top-level section, which usually contains a single entry
+    /// such as `"AppState" { ... }`.
+    pub fn parse(input: &str) -> Result<KeyValues> {
+        let tokens = tokenize(input)?;
+        let mut position = 0;
+        let root = parse_section(&tokens, &mut position, true)?;
+
+        Ok(KeyValues::Section(root))
+    }
+
+    /// Parse only the first key/value pair of the input and ignore anything that follows.
+    ///
+    /// This is useful for documents that're embedded in other output, such as the app info that's
+    /// printed by steamcmd.
+    pub fn parse_first(input: &str) -> Result<KeyValues> {
+        let tokens = tokenize(input)?;
+        let mut position = 0;
+        let entry = parse_entry(&tokens, &mut position)?;
+
+        Ok(KeyValues::Section(vec![entry]))
+    }
+
+    /// Get the child with the given key. Keys are compared case-insensitively, just like Valve
+    /// does it.
+    pub fn get(&self, key: &str) -> Option<&KeyValues> {
+        match self {
+            KeyValues::Value(_) => None,
+            KeyValues::Section(entries) => entries
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value),
+        }
+    }
+
+    /// Follow a path of keys, e.g. `["AppState", "buildid"]`.
+    pub fn get_path(&self, path: &[&str]) -> Option<&KeyValues> {
+        path.iter().try_fold(self, |node, key| node.get(key))
+    }
+
+    /// Return the string value of this node, if it's a value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            KeyValues::Value(value) => Some(value),
+            KeyValues::Section(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    String(String),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some(escaped) => string.push(escaped),
+                            None => bail!("Unexpected end of input after escape character"),
+                        },
+                        Some(char) => string.push(char),
+                        None => bail!("Unterminated string in KeyValues document"),
+                    }
+                }
+                tokens.push(Token::String(string));
+            }
+            // Line comments
+            '/' if chars.peek() == Some(&'/') => {
+                for char in chars.by_ref() {
+                    if char == '\n' {
+                        break;
+                    }
+                }
+            }
+            // Conditionals such as `[$WIN32]` aren't relevant for us and are skipped.
+            '[' => {
+                for char in chars.by_ref() {
+                    if char == ']' {
+                        break;
+                    }
+                }
+            }
+            char if char.is_whitespace() => continue,
+            // Unquoted strings
+            char => {
+                let mut string = String::from(char);
+                while let Some(next) = chars.peek() {
+                    if next.is_whitespace() || matches!(next, '{' | '}' | '"') {
+                        break;
+                    }
+                    string.push(*next);
+                    chars.next();
+                }
+                tokens.push(Token::String(string));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_section(
+    tokens: &[Token],
+    position: &mut usize,
+    top_level: bool,
+) -> Result<Vec<(String, KeyValues)>> {
+    let mut entries = Vec::new();
+
+    loop {
+        match tokens.get(*position) {
+            Some(Token::String(_)) => entries.push(parse_entry(tokens, position)?),
+            Some(Token::Close) if !top_level => {
+                *position += 1;
+                return Ok(entries);
+            }
+            None if top_level => return Ok(entries),
+            Some(token) => bail!("Unexpected token {token:?} in KeyValues document"),
+            None => bail!("Unexpected end of KeyValues document, missing '}}'"),
+        }
+    }
+}
+
+fn parse_entry(tokens: &[Token], position: &mut usize) -> Result<(String, KeyValues)> {
+    let Some(Token::String(key)) = tokens.get(*position) else {
+        bail!("Expected a key in KeyValues document");
+    };
+    *position += 1;
+
+    let value = match tokens.get(*position) {
+        Some(Token::String(value)) => {
+            *position += 1;
+            KeyValues::Value(value.clone())
+        }
+        Some(Token::Open) => {
+            *position += 1;
+            KeyValues::Section(parse_section(tokens, position, false)?)
+        }
+        _ => bail!("Missing value for key '{key}' in KeyValues document"),
+    };
+
+    Ok((key.clone(), value))
+}
+
+/// Read the build id of the public branch from the app info that's printed by steamcmd.
+///
+/// steamcmd prints a lot of noise before the actual app info, which starts with a line that only
+/// contains the quoted app id, followed by a `{ ... }` block.
+fn public_build_id(output: &str, app_id: u32) -> Result<String> {
+    let key = format!("\"{app_id}\"");
+    let mut offset = 0;
+    for line in output.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        if line.trim() != key {
+            continue;
+        }
+
+        // Other lines might contain the quoted app id as well, so only accept a block that
+        // actually contains the build id.
+        let Ok(app_info) = KeyValues::parse_first(&output[start..]) else {
+            continue;
+        };
+        if let Some(build_id) = app_info
+            .get_path(&[
+                &app_id.to_string(),
+                "depots",
+                "branches",
+                "public",
+                "buildid",
+            ])
+            .and_then(KeyValues::as_str)
+        {
+            return Ok(build_id.to_string());
+        }
+    }
+
+    bail!("Couldn't find the public buildid of {app_id} in the steamcmd output")
+}
+
+/// Common functionality for games that're installed and updated via `steamcmd`.
+pub trait SteamServer: GameServer {
+    /// The app id of the dedicated server.
+    fn app_id(&self) -> u32;
+
+    /// The platform that should be forced during installation, e.g. `windows` for games that
+    /// only ship a windows server binary and run via wine.
+    fn platform(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the output of steamcmd is shown while the server is installed or updated.
+    fn show_steamcmd_output(&self) -> bool {
+        true
+    }
+
+    /// The path to the app manifest of the installed server.
+    fn app_manifest_path(&self) -> PathBuf {
+        self.config()
+            .game_dir()
+            .join("steamapps")
+            .join(format!("appmanifest_{}.acf", self.app_id()))
+    }
+
+    /// Read the build id of the currently installed server from its app manifest.
+    ///
+    /// Returns `None` if the game isn't installed yet.
+    fn installed_build_id(&self) -> Result<Option<String>> {
+        let path = self.app_manifest_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content =
+            read_to_string(&path).wrap_err(format!("Failed to read app manifest {path:?}"))?;
+        let manifest = KeyValues::parse(&content)
+            .wrap_err(format!("Failed to parse app manifest {path:?}"))?;
+
+        let build_id = manifest
+            .get_path(&["AppState", "buildid"])
+            .and_then(KeyValues::as_str)
+            .ok_or_else(|| eyre!("Couldn't find buildid in app manifest {path:?}"))?;
+
+        Ok(Some(build_id.to_string()))
+    }
+
+    /// Ask steamcmd for the build id of the latest public release.
+    fn remote_build_id(&self) -> Result<String> {
+        let app_id = self.app_id();
+        let output =
+            cmd!("steamcmd +login anonymous +app_info_update 1 +app_info_print {app_id} +quit")
+                .run_success()
+                .wrap_err("Failed to get app info via steamcmd")?;
+        let output = String::from_utf8_lossy(&output.stdout);
+
+        public_build_id(&output, app_id)
+    }
+
+    /// Check whether the remote build differs from the installed one.
+    fn update_available(&self) -> Result<bool> {
+        let installed = self.installed_build_id()?;
+        let remote = self.remote_build_id()?;
+
+        match installed {
+            Some(installed) => {
+                info!(
+                    "{} - Installed build: {installed}, latest build: {remote}",
+                    self.session_name()
+                );
+                Ok(installed != remote)
+            }
+            None => {
+                info!(
+                    "{} - Not installed yet, latest build: {remote}",
+                    self.session_name()
+                );
+                Ok(true)
+            }
+        }
+    }
+
+    /// Check for a new build and report the result.
+    fn check_update(&self) -> Result<()> {
+        if self.update_available()? {
+            info!("{} - A new build is available", self.session_name());
+        } else {
+            info!("{} - Server is up to date", self.session_name());
+        }
+
+        Ok(())
+    }
+
+    /// Only run the update if a new build is available.
+    fn update_if_needed(&self) -> Result<()> {
+        if !self.update_available()? {
+            info!("{} - Server is already up to date", self.session_name());
+            return Ok(());
+        }
+
+        self.update()
+    }
+
+    /// Install or update the server files via steamcmd.
+    fn steamcmd_update(&self) -> Result<()> {
+        let platform = self
+            .platform()
+            .map(|platform| format!("+@sSteamCmdForcePlatformType {platform} "))
+            .unwrap_or_default();
+
+        let mut command = cmd!(
+            r#"steamcmd \
+        {platform}+force_install_dir {} \
+        +login anonymous \
+        +app_update {} \
+        validate +quit"#,
+            self.config().game_dir_str(),
+            self.app_id()
+        );
+        if self.show_steamcmd_output() {
+            command = command.io_passthrough();
+        }
+        command.run_success()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP_MANIFEST: &str = r#"
+"AppState"
+{
+	"appid"		"4020"
+	"name"		"Garry's Mod Dedicated Server"
+	// The build that's installed
+	"buildid"		"14883417"
+	"InstalledDepots"
+	{
+		"4023"
+		{
+			"manifest"		"6187612178573637417"
+			"size"		"1054716049"
+		}
+	}
+	"UserConfig"
+	{
+		"language"		"english"
+	}
+}
+"#;
+
+    #[test]
+    fn parse_app_manifest() -> Result<()> {
+        let manifest = KeyValues::parse(APP_MANIFEST)?;
+
+        assert_eq!(
+            manifest
+                .get_path(&["AppState", "buildid"])
+                .and_then(KeyValues::as_str),
+            Some("14883417")
+        );
+        // Keys are case-insensitive.
+        assert_eq!(
+            manifest
+                .get_path(&["appstate", "installeddepots", "4023", "size"])
+                .and_then(KeyValues::as_str),
+            Some("1054716049")
+        );
+        assert!(manifest.get_path(&["AppState", "missing"]).is_none());
+        assert!(
+            manifest
+                .get_path(&["AppState", "appid", "nested"])
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_escapes_conditionals_and_unquoted_strings() -> Result<()> {
+        let document = KeyValues::parse(
+            r#"
+"root"
+{
+    "text" "say \"hi\"\n"
+    unquoted value
+    "platform" "linux" [$LINUX]
+}
+"#,
+        )?;
+
+        let root = document.get("root").unwrap();
+        assert_eq!(
+            root.get("text").and_then(KeyValues::as_str),
+            Some("say \"hi\"\n")
+        );
+        assert_eq!(
+            root.get("unquoted").and_then(KeyValues::as_str),
+            Some("value")
+        );
+        assert_eq!(
+            root.get("platform").and_then(KeyValues::as_str),
+            Some("linux")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_documents() {
+        assert!(KeyValues::parse(r#""root" { "key" "value""#).is_err());
+        assert!(KeyValues::parse(r#""root" { "key" "value"#).is_err());
+        assert!(KeyValues::parse(r#""key""#).is_err());
+        assert!(KeyValues::parse("}").is_err());
+    }
+
+    #[test]
+    fn parse_first_ignores_trailing_output() -> Result<()> {
+        let document = KeyValues::parse_first(r#""730" { "common" { "name" "CS2" } } trailing {"#)?;
+
+        assert_eq!(
+            document
+                .get_path(&["730", "common", "name"])
+                .and_then(KeyValues::as_str),
+            Some("CS2")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn public_build_id_from_steamcmd_output() -> Result<()> {
+        let output = r#"Redirecting stderr to '/home/steam/Steam/logs/stderr.txt'
+Loading Steam API...OK
+Connecting anonymously to Steam Public...OK
+AppID : 730, change number : 27839214/0, last change : Tue Oct 14 2026
+"730" was requested, printing it below:
+"730"
+{
+	"common"
+	{
+		"name"		"Counter-Strike 2"
+	}
+	"depots"
+	{
+		"branches"
+		{
+			"public"
+			{
+				"buildid"		"20450232"
+				"timeupdated"		"1760457622"
+			}
+			"beta"
+			{
+				"buildid"		"20460001"
+			}
+		}
+	}
+}
+Unloading Steam API...OK
+"#;
+
+        assert_eq!(public_build_id(output, 730)?, "20450232");
+        assert!(public_build_id(output, 740).is_err());
+        assert!(public_build_id("\"730\"\n{ \"common\" {} }\n", 730).is_err());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,13 @@
-use std::{collections::HashMap, path::PathBuf, process::Output};
+use std::{collections::HashMap, path::PathBuf, process::Output, time::Duration};
 
-use crate::{cmd, errors::*, prelude::GameServer, process::*};
+use crate::{
+    cmd,
+    errors::*,
+    network::{Protocol, parent_pid, port_owners},
+    prelude::GameServer,
+    process::*,
+    rcon::{self, RconClient},
+};
 
 pub trait TmuxServer: GameServer {
     /// Spawn a new tmux session based on the game name and optional instance.
@@ -14,6 +21,50 @@ pub trait TmuxServer: GameServer {
             .wrap_err(format!("Failed to spawn session {}", self.session_name()))
     }
 
+    /// Get the ids of the processes that run in the session's panes, usually the shells.
+    fn session_pids(&self) -> Result<Vec<u32>> {
+        let output = cmd!(
+            "tmux list-panes -t {} -F '#{{pane_pid}}'",
+            self.session_name()
+        )
+        .run_success()
+        .wrap_err(format!(
+            "Failed to get processes of session {}",
+            self.session_name()
+        ))?;
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect())
+    }
+
+    /// Check whether the port is bound by a process that has been started in this session.
+    ///
+    /// Unlike [crate::network::is_port_bound], this isn't fooled by another server that uses the
+    /// same port.
+    fn is_port_bound_by_session(&self, port: u16, protocol: Protocol) -> Result<bool> {
+        if !self.is_session_open()? {
+            return Ok(false);
+        }
+
+        let session_pids = self.session_pids()?;
+        for owner in port_owners(port, protocol)? {
+            // Walk up the process tree until we either hit the session or the init process.
+            let mut pid = Some(owner);
+            while let Some(current) = pid
+                && current > 1
+            {
+                if session_pids.contains(&current) {
+                    return Ok(true);
+                }
+                pid = parent_pid(current);
+            }
+        }
+
+        Ok(false)
+    }
+
     fn is_session_open(&self) -> Result<bool> {
         let output = cmd!("tmux has-session -t {}", self.session_name()).run()?;
         Ok(output.status.success())
@@ -74,6 +125,42 @@ pub trait TmuxServer: GameServer {
             .wrap_err(format!("Failed to send newline to {}", self.session_name()))
     }
 
+    /// Send a command to the server's console.
+    ///
+    /// If RCON is configured, the command is sent via RCON and its output is returned.
+    /// Otherwise, or if no RCON connection can be established, the command is typed into the tmux
+    /// session and there's no output.
+    fn send_command(&self, command: &str) -> Result<Option<String>> {
+        self.send_command_with_timeout(command, rcon::TIMEOUT)
+    }
+
+    /// Same as [TmuxServer::send_command], but waits up to `timeout` for the RCON response.
+    fn send_command_with_timeout(
+        &self,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<Option<String>> {
+        if let Some(settings) = self.rcon_settings()? {
+            match RconClient::connect(&settings) {
+                Ok(mut client) => {
+                    let output = client
+                        .command_with_timeout(command, timeout)
+                        .wrap_err(format!("Failed to send RCON command: {command}"))?;
+
+                    return Ok(Some(output));
+                }
+                // The server might still be starting up, in which case RCON isn't available yet.
+                Err(error) => warn!(
+                    "{} - Failed to connect via RCON, falling back to the console without output: {error:#}",
+                    self.session_name()
+                ),
+            }
+        }
+
+        self.send_input_newline(command)?;
+        Ok(None)
+    }
+
     /// Send a Ctrl-c to a session.
     fn send_ctrl_c(&self) -> Result<Output> {
         cmd!("tmux send-keys -t {} C-c", self.session_name())
@@ -0,0 +1,251 @@
+use std::{collections::BTreeSet, fmt};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{errors::*, http};
+
+/// The workshop file type of collections. All other types are treated as items.
+const COLLECTION_FILE_TYPE: u32 = 2;
+
+/// A single item of the Steam workshop.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct WorkshopItem {
+    pub id: String,
+    pub title: String,
+    /// The unix timestamp of the item's last update.
+    pub time_updated: u64,
+}
+
+impl fmt::Display for WorkshopItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.title, self.id)
+    }
+}
+
+/// The changes of a collection's items since the last sync.
+#[derive(Clone, Debug, Default)]
+pub struct WorkshopReport {
+    pub added: Vec<WorkshopItem>,
+    pub updated: Vec<WorkshopItem>,
+    pub removed: Vec<WorkshopItem>,
+    pub unchanged: usize,
+}
+
+impl WorkshopReport {
+    /// Compare the items of the last sync with the current ones.
+    pub fn new(previous: &[WorkshopItem], current: &[WorkshopItem]) -> Self {
+        let mut report = WorkshopReport::default();
+        for item in current {
+            match previous.iter().find(|previous| previous.id == item.id) {
+                None => report.added.push(item.clone()),
+                Some(previous) if previous.time_updated != item.time_updated => {
+                    report.updated.push(item.clone())
+                }
+                Some(_) => report.unchanged += 1,
+            }
+        }
+        report.removed = previous
+            .iter()
+            .filter(|previous| !current.iter().any(|item| item.id == previous.id))
+            .cloned()
+            .collect();
+
+        report
+    }
+
+    /// Whether an item has been added or updated since the last sync.
+    pub fn is_changed(&self, item: &WorkshopItem) -> bool {
+        self.added
+            .iter()
+            .chain(self.updated.iter())
+            .any(|changed| changed.id == item.id)
+    }
+}
+
+impl fmt::Display for WorkshopReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.added {
+            writeln!(f, "Added: {item}")?;
+        }
+        for item in &self.updated {
+            writeln!(f, "Updated: {item}")?;
+        }
+        for item in &self.removed {
+            writeln!(f, "Removed: {item}")?;
+        }
+        write!(f, "{} items are unchanged", self.unchanged)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    response: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct CollectionDetails {
+    #[serde(default)]
+    collectiondetails: Vec<Collection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Collection {
+    publishedfileid: String,
+    result: u32,
+    #[serde(default)]
+    children: Vec<CollectionChild>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CollectionChild {
+    publishedfileid: String,
+    filetype: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDetails {
+    #[serde(default)]
+    publishedfiledetails: Vec<FileDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDetail {
+    publishedfileid: String,
+    result: u32,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    time_updated: u64,
+}
+
+/// Build the form parameters for a list of published file ids, as expected by the Web API.
+fn id_form(count_key: &str, ids: &[String]) -> Vec<(String, String)> {
+    let mut form = vec![(count_key.to_string(), ids.len().to_string())];
+    for (index, id) in ids.iter().enumerate() {
+        form.push((format!("publishedfileids[{index}]"), id.clone()));
+    }
+    form
+}
+
+/// Get all items of a workshop collection via the Steam Web API.
+///
+/// Collections can contain other collections, whose items are included as well.
+/// Items that have been removed from the workshop are skipped.
+pub fn collection_items(api_url: &str, collection: u64) -> Result<Vec<WorkshopItem>> {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![collection.to_string()];
+    let mut item_ids: Vec<String> = Vec::new();
+    while !pending.is_empty() {
+        seen.extend(pending.iter().cloned());
+
+        let url = format!("{api_url}/ISteamRemoteStorage/GetCollectionDetails/v1/");
+        let details: ApiResponse<CollectionDetails> =
+            http::post_form_json(&url, &id_form("collectioncount", &pending))
+                .wrap_err(format!("Failed to get workshop collection {collection}"))?;
+
+        pending = Vec::new();
+        for collection in details.response.collectiondetails {
+            if collection.result != 1 {
+                bail!(
+                    "Couldn't find workshop collection {}",
+                    collection.publishedfileid
+                );
+            }
+            for child in collection.children {
+                if child.filetype == COLLECTION_FILE_TYPE {
+                    if !seen.contains(&child.publishedfileid) {
+                        pending.push(child.publishedfileid);
+                    }
+                } else if !item_ids.contains(&child.publishedfileid) {
+                    item_ids.push(child.publishedfileid);
+                }
+            }
+        }
+    }
+
+    if item_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let url = format!("{api_url}/ISteamRemoteStorage/GetPublishedFileDetails/v1/");
+    let details: ApiResponse<FileDetails> =
+        http::post_form_json(&url, &id_form("itemcount", &item_ids)).wrap_err(format!(
+            "Failed to get items of workshop collection {collection}"
+        ))?;
+
+    let mut items = Vec::new();
+    for detail in details.response.publishedfiledetails {
+        if detail.result != 1 {
+            warn!(
+                "Skipping workshop item {}, which isn't available anymore",
+                detail.publishedfileid
+            );
+            continue;
+        }
+        items.push(WorkshopItem {
+            id: detail.publishedfileid,
+            title: detail.title,
+            time_updated: detail.time_updated,
+        });
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, time_updated: u64) -> WorkshopItem {
+        WorkshopItem {
+            id: id.to_string(),
+            title: format!("Item {id}"),
+            time_updated,
+        }
+    }
+
+    #[test]
+    fn report() {
+        let previous = [item("1", 100), item("2", 100), item("3", 100)];
+        let current = [item("1", 100), item("2", 200), item("4", 100)];
+        let report = WorkshopReport::new(&previous, &current);
+
+        assert_eq!(report.added, [item("4", 100)]);
+        assert_eq!(report.updated, [item("2", 200)]);
+        assert_eq!(report.removed, [item("3", 100)]);
+        assert_eq!(report.unchanged, 1);
+
+        assert!(report.is_changed(&item("4", 100)));
+        assert!(report.is_changed(&item("2", 200)));
+        assert!(!report.is_changed(&item("1", 100)));
+        assert!(!report.is_changed(&item("3", 100)));
+
+        assert_eq!(
+            report.to_string(),
+            "Added: Item 4 (4)\nUpdated: Item 2 (2)\nRemoved: Item 3 (3)\n1 items are unchanged"
+        );
+    }
+
+    #[test]
+    fn first_sync() {
+        let current = [item("1", 100), item("2", 100)];
+        let report = WorkshopReport::new(&[], &current);
+        assert_eq!(report.added, current);
+        assert!(report.updated.is_empty());
+        assert!(report.removed.is_empty());
+        assert_eq!(report.unchanged, 0);
+    }
+
+    #[test]
+    fn form() {
+        let ids = ["1".to_string(), "2".to_string()];
+        assert_eq!(
+            id_form("itemcount", &ids),
+            [
+                ("itemcount".to_string(), "2".to_string()),
+                ("publishedfileids[0]".to_string(), "1".to_string()),
+                ("publishedfileids[1]".to_string(), "2".to_string()),
+            ]
+        );
+    }
+}